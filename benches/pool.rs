@@ -0,0 +1,36 @@
+//! Allocation cost at increasing pool occupancy.
+//!
+//! Run with `cargo bench --bench pool`.
+
+use myser::pool::{Heap, Pool, RcValue};
+use std::hint::black_box;
+use std::time::Instant;
+
+const CELLS: usize = 10000;
+const ROUNDS: usize = 100000;
+
+fn churn(occupancy: usize) {
+    let pool: Pool<'_, CELLS> = Pool::new();
+
+    // Values outside the immediate range, so that each one takes a cell.
+    let held: Vec<RcValue> = (0..occupancy).map(|n| pool.new_integer(1000 + n as i64)).collect();
+
+    let start = Instant::now();
+    for n in 0..ROUNDS {
+        black_box(pool.new_integer(1000 + n as i64));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:>5} / {} live: {:>8.1} ns per allocation",
+        held.len(),
+        CELLS,
+        elapsed.as_nanos() as f64 / ROUNDS as f64
+    );
+}
+
+fn main() {
+    for occupancy in [0, CELLS / 2, CELLS * 9 / 10, CELLS * 99 / 100, CELLS - 1] {
+        churn(occupancy);
+    }
+}
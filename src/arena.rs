@@ -0,0 +1,525 @@
+use crate::pool::{shared::{Counter, Lock, Slot}, RcValue};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+use core::{fmt, str};
+
+/// The start of every block. While the block is in use, `len` is the length
+/// of its contents and `link` points at its arena's ledger; while it is free,
+/// `len` is the size of the whole block and `link` the next free block.
+#[repr(C, align(8))]
+struct Header {
+    len: usize,
+    link: *const (),
+}
+
+/// Blocks are a whole number of granules, so a block split off the end of
+/// another always has room for its own header.
+const GRANULE: usize = mem::size_of::<Header>();
+
+fn block_size(len: usize) -> Option<usize> {
+    len.checked_add(GRANULE + GRANULE - 1).map(|size| size / GRANULE * GRANULE)
+}
+
+/// Bookkeeping for the blocks of an `Arena`, or with the `alloc` feature of
+/// blocks taken from the global allocator.
+///
+/// Free blocks of an arena are kept in address order so that a freed block
+/// merges with its neighbours, and a free block at the end of the used
+/// space is given back to it. An all-zero `Ledger` is a valid, empty one.
+pub(crate) struct Ledger {
+    /// The first byte of the arena, set on every allocation in case the
+    /// arena has moved since it was created.
+    start: Slot<u8>,
+    /// Bytes from `start + fresh` onwards have never been handed out.
+    fresh: Counter,
+    free: Slot<Header>,
+    /// Bytes in blocks that are in use, headers included.
+    used: Counter,
+    lock: Lock,
+    #[cfg(feature = "alloc")]
+    global: bool,
+}
+
+impl Ledger {
+    pub(crate) const fn new() -> Self {
+        Ledger {
+            start: Slot::null(),
+            fresh: Counter::new(0),
+            free: Slot::null(),
+            used: Counter::new(0),
+            lock: Lock::new(),
+            #[cfg(feature = "alloc")]
+            global: false,
+        }
+    }
+
+    /// A ledger for blocks from the global allocator, which has no bound.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn global() -> Self {
+        Ledger { global: true, ..Ledger::new() }
+    }
+
+    pub(crate) fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Hands out a zeroed block with room for `len` bytes, from the arena at
+    /// `start` of `capacity` bytes.
+    fn alloc(&self, start: *mut u8, capacity: usize, len: usize) -> Option<NonNull<Header>> {
+        let size = block_size(len)?;
+
+        #[cfg(feature = "alloc")]
+        if self.global {
+            let layout = core::alloc::Layout::from_size_align(size, GRANULE).ok()?;
+            let block = NonNull::new(unsafe { alloc::alloc::alloc_zeroed(layout) } as *mut Header)?;
+            self.lock.with(|| self.used.set(self.used.get() + size));
+            return Some(self.hand_out(block, len));
+        }
+
+        let block = self.lock.with(|| unsafe { self.take(start, capacity, size) })?;
+        unsafe {
+            (block.as_ptr() as *mut u8).add(GRANULE).write_bytes(0, size - GRANULE);
+        }
+
+        Some(self.hand_out(block, len))
+    }
+
+    fn hand_out(&self, block: NonNull<Header>, len: usize) -> NonNull<Header> {
+        unsafe {
+            block.as_ptr().write(Header { len, link: self as *const Ledger as *const () });
+        }
+
+        block
+    }
+
+    /// Takes `size` bytes from the first free block big enough, or from the
+    /// never-used end of the arena. Must be called under the lock.
+    unsafe fn take(&self, start: *mut u8, capacity: usize, size: usize) -> Option<NonNull<Header>> {
+        self.start.set(start);
+
+        let mut prev: *mut Header = ptr::null_mut();
+        let mut block = self.free.get() as *mut Header;
+        while !block.is_null() {
+            let Header { len: free, link: next } = block.read();
+
+            if free >= size {
+                let rest = if free > size {
+                    let rest = (block as *mut u8).add(size) as *mut Header;
+                    rest.write(Header { len: free - size, link: next });
+                    rest
+                } else {
+                    next as *mut Header
+                };
+
+                match prev.as_mut() {
+                    Some(prev) => prev.link = rest as *const (),
+                    None => self.free.set(rest),
+                }
+                self.used.set(self.used.get() + size);
+
+                return NonNull::new(block);
+            }
+
+            prev = block;
+            block = next as *mut Header;
+        }
+
+        let fresh = self.fresh.get();
+        if capacity - fresh < size {
+            return None;
+        }
+
+        self.fresh.set(fresh + size);
+        self.used.set(self.used.get() + size);
+        NonNull::new(start.add(fresh) as *mut Header)
+    }
+
+    /// Returns a block to the free list, merging it with its neighbours.
+    unsafe fn release(&self, block: NonNull<Header>) {
+        let block = block.as_ptr();
+        let size = block_size((*block).len).unwrap();
+
+        #[cfg(feature = "alloc")]
+        if self.global {
+            self.lock.with(|| self.used.set(self.used.get() - size));
+            let layout = core::alloc::Layout::from_size_align(size, GRANULE).unwrap();
+            alloc::alloc::dealloc(block as *mut u8, layout);
+            return;
+        }
+
+        self.lock.with(|| {
+            self.used.set(self.used.get() - size);
+
+            let end = |block: *mut Header| (block as *mut u8).add((*block).len) as *mut Header;
+
+            // The free blocks on either side of this one, and the one before those.
+            let mut before: *mut Header = ptr::null_mut();
+            let mut prev: *mut Header = ptr::null_mut();
+            let mut next = self.free.get() as *mut Header;
+            while !next.is_null() && next < block {
+                before = prev;
+                prev = next;
+                next = (*next).link as *mut Header;
+            }
+
+            (*block).len = size;
+            (*block).link = next as *const ();
+            if next == end(block) {
+                (*block).len += (*next).len;
+                (*block).link = (*next).link;
+            }
+
+            let (before, block) = if prev.is_null() {
+                self.free.set(block);
+                (ptr::null_mut(), block)
+            } else if end(prev) == block {
+                (*prev).len += (*block).len;
+                (*prev).link = (*block).link;
+                (before, prev)
+            } else {
+                (*prev).link = block as *const ();
+                (prev, block)
+            };
+
+            let start = self.start.get() as *mut u8;
+            if end(block) as *mut u8 == start.add(self.fresh.get()) {
+                match before.as_mut() {
+                    Some(before) => before.link = ptr::null(),
+                    None => self.free.set(ptr::null()),
+                }
+                self.fresh.set((block as *mut u8).offset_from(start) as usize);
+            }
+        })
+    }
+
+    /// Checks the free list against the arena's bounds and counts.
+    pub(crate) fn verify(&self) -> Result<(), &'static str> {
+        self.lock.with(|| {
+            let start = self.start.get();
+            let end = start.wrapping_add(self.fresh.get());
+
+            let mut free = 0;
+            let mut last: *const u8 = ptr::null();
+            let mut block = self.free.get();
+            while !block.is_null() {
+                let at = block as *const u8;
+                if at < start || at >= end {
+                    return Err("arena free list leaves the arena");
+                }
+                if at < last {
+                    return Err("arena free list is out of order");
+                }
+                if at == last {
+                    return Err("arena free list holds neighbours that were not merged");
+                }
+
+                let Header { len, link } = unsafe { block.read() };
+                free += len;
+                last = at.wrapping_add(len);
+                block = link as *const Header;
+            }
+
+            if free > 0 && last == end {
+                return Err("arena free list holds the end of the arena");
+            }
+            if free + self.used.get() != self.fresh.get() {
+                return Err("arena bytes in use disagree with the free list");
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[repr(C, align(8))]
+struct Storage<const BYTES: usize>(UnsafeCell<[MaybeUninit<u8>; BYTES]>);
+
+/// A fixed region of `BYTES` bytes handing out blocks of any size, for values
+/// too big for a cell. An all-zero `Arena` is a valid, empty arena.
+///
+/// Each block costs a header of two words on top of its contents, rounded
+/// up to a multiple of the header's size.
+pub struct Arena<const BYTES: usize> {
+    storage: Storage<BYTES>,
+    ledger: Ledger,
+}
+
+impl<const BYTES: usize> Default for Arena<BYTES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BYTES: usize> Arena<BYTES> {
+    pub const fn new() -> Self {
+        Arena {
+            storage: Storage(UnsafeCell::new([MaybeUninit::uninit(); BYTES])),
+            ledger: Ledger::new(),
+        }
+    }
+
+    /// A zeroed block of `len` bytes, or `None` if no free space is big enough.
+    pub fn alloc(&self, len: usize) -> Option<Block<'_>> {
+        let start = self.storage.0.get() as *mut u8;
+        let header = self.ledger.alloc(start, BYTES, len)?;
+
+        Some(Block { header, lifetime: PhantomData })
+    }
+
+    /// Bytes in use, headers included.
+    pub fn used(&self) -> usize {
+        self.ledger.used()
+    }
+
+    pub fn verify(&self) -> Result<(), &'static str> {
+        self.ledger.verify()
+    }
+}
+
+/// Allocates a block from the global allocator, accounted for in `ledger`.
+#[cfg(feature = "alloc")]
+pub(crate) fn alloc_global<'s>(ledger: &'s Ledger, len: usize) -> Option<Block<'s>> {
+    let header = ledger.alloc(ptr::null_mut(), 0, len)?;
+
+    Some(Block { header, lifetime: PhantomData })
+}
+
+/// Bytes owned by one value, returned to their arena when dropped.
+pub struct Block<'s> {
+    header: NonNull<Header>,
+    lifetime: PhantomData<&'s Ledger>,
+}
+
+#[cfg(feature = "sync")]
+unsafe impl<'s> Send for Block<'s> {}
+#[cfg(feature = "sync")]
+unsafe impl<'s> Sync for Block<'s> {}
+
+impl<'s> Block<'s> {
+    pub fn len(&self) -> usize {
+        unsafe { (*self.header.as_ptr()).len }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts((self.header.as_ptr() as *const u8).add(GRANULE), self.len()) }
+    }
+
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.as_ptr(), self.len()) }
+    }
+
+    /// The first byte of the contents, for blocks whose owners manage
+    /// access themselves.
+    pub(crate) fn as_ptr(&self) -> *mut u8 {
+        unsafe { (self.header.as_ptr() as *mut u8).add(GRANULE) }
+    }
+
+    /// Gives up the block without freeing it, returning its first byte. It
+    /// stays in use until `from_raw` takes it back.
+    pub(crate) fn into_raw(self) -> *mut u8 {
+        let ptr = self.as_ptr();
+        mem::forget(self);
+        ptr
+    }
+
+    /// Takes back a block given up by `into_raw`.
+    pub(crate) unsafe fn from_raw(ptr: *mut u8) -> Self {
+        Block {
+            header: NonNull::new_unchecked(ptr.sub(GRANULE) as *mut Header),
+            lifetime: PhantomData,
+        }
+    }
+}
+
+impl<'s> Drop for Block<'s> {
+    fn drop(&mut self) {
+        unsafe {
+            let ledger = &*((*self.header.as_ptr()).link as *const Ledger);
+            ledger.release(self.header);
+        }
+    }
+}
+
+impl<'s> fmt::Debug for Block<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bytes().fmt(f)
+    }
+}
+
+/// A string owned by a value, held in a `Block`.
+pub struct Text<'s>(Block<'s>);
+
+impl<'s> Text<'s> {
+    pub fn from_utf8(block: Block<'s>) -> Result<Self, Block<'s>> {
+        match str::from_utf8(block.bytes()) {
+            Ok(_) => Ok(Text(block)),
+            Err(_) => Err(block),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The block must hold valid UTF-8.
+    pub unsafe fn from_utf8_unchecked(block: Block<'s>) -> Self {
+        Text(block)
+    }
+
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.0.bytes()) }
+    }
+}
+
+impl<'s> Deref for Text<'s> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'s> fmt::Debug for Text<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// The elements of a vector value: a `Block` of `RcValue`s, which can be
+/// replaced in place.
+pub struct Vector<'s> {
+    block: Block<'s>,
+    lock: Lock,
+}
+
+impl<'s> Vector<'s> {
+    /// Bytes in a block for `len` elements.
+    pub fn size(len: usize) -> Option<usize> {
+        len.checked_mul(mem::size_of::<RcValue>())
+    }
+
+    /// Fills `block`, of `size(len)` bytes, with elements made by `f`,
+    /// called with each index in order.
+    pub fn from_fn(block: Block<'s>, mut f: impl FnMut(usize) -> RcValue<'s>) -> Self {
+        let slots = block.as_ptr() as *mut RcValue<'s>;
+        for index in 0..block.len() / mem::size_of::<RcValue>() {
+            unsafe { slots.add(index).write(f(index)) }
+        }
+
+        Vector { block, lock: Lock::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.block.len() / mem::size_of::<RcValue>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<RcValue<'s>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let slots = self.block.as_ptr() as *const RcValue<'s>;
+        Some(self.lock.with(|| unsafe { (*slots.add(index)).clone() }))
+    }
+
+    /// Replaces the element at `index`, handing `value` back if there is none.
+    pub fn set(&self, index: usize, value: RcValue<'s>) -> Result<(), RcValue<'s>> {
+        if index >= self.len() {
+            return Err(value);
+        }
+
+        let slots = self.block.as_ptr() as *mut RcValue<'s>;
+        let old = self.lock.with(|| unsafe { slots.add(index).replace(value) });
+        drop(old);
+
+        Ok(())
+    }
+}
+
+impl<'s> Drop for Vector<'s> {
+    fn drop(&mut self) {
+        let slots = self.block.as_ptr() as *mut RcValue<'s>;
+        for index in 0..self.len() {
+            unsafe { ptr::drop_in_place(slots.add(index)) }
+        }
+    }
+}
+
+impl<'s> fmt::Debug for Vector<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries((0..self.len()).filter_map(|index| self.get(index))).finish()
+    }
+}
+
+/// The contents of a byte buffer value: a `Block` of bytes, which can be
+/// overwritten in place.
+pub struct Bytes<'s> {
+    block: Block<'s>,
+    lock: Lock,
+}
+
+impl<'s> Bytes<'s> {
+    /// Fills `block` with bytes made by `f`, called with each index in
+    /// order.
+    pub fn from_fn(mut block: Block<'s>, mut f: impl FnMut(usize) -> u8) -> Self {
+        for (index, byte) in block.bytes_mut().iter_mut().enumerate() {
+            *byte = f(index);
+        }
+
+        Bytes { block, lock: Lock::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.block.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.block.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<u8> {
+        let mut byte = [0];
+        self.read(index, &mut byte).then_some(byte[0])
+    }
+
+    /// Copies the bytes from `offset` into `bytes`, unless that would run
+    /// past the end, returning whether it did.
+    pub fn read(&self, offset: usize, bytes: &mut [u8]) -> bool {
+        if offset.checked_add(bytes.len()).is_none_or(|end| end > self.len()) {
+            return false;
+        }
+
+        let from = self.block.as_ptr() as *const u8;
+        self.lock.with(|| unsafe { ptr::copy_nonoverlapping(from.add(offset), bytes.as_mut_ptr(), bytes.len()) });
+
+        true
+    }
+
+    /// Copies `bytes` over those from `offset`, unless that would run past
+    /// the end, returning whether it did.
+    pub fn write(&self, offset: usize, bytes: &[u8]) -> bool {
+        if offset.checked_add(bytes.len()).is_none_or(|end| end > self.len()) {
+            return false;
+        }
+
+        let to = self.block.as_ptr();
+        self.lock.with(|| unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), to.add(offset), bytes.len()) });
+
+        true
+    }
+}
+
+impl<'s> fmt::Debug for Bytes<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries((0..self.len()).filter_map(|index| self.get(index))).finish()
+    }
+}
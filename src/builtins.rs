@@ -1,216 +1,1231 @@
-use crate::{pool::{Pool, RcValue}, value::Value};
+use crate::{arena::{Bytes, Text, Vector}, eval::{Env, Name, Unwind}, number::{Failure, Number, Op}, parser, pool::{Heap, RcValue}, table::Table, value::{Value, KINDS}};
+use core::cmp::Ordering;
+use core::fmt::Write;
 use core::ops::Deref;
 use heapless::FnvIndexMap;
 
-pub fn add<'s, Context, const N: usize>(_: &mut Context, pool: &'s Pool<'s, N>, args: RcValue<'s>) -> RcValue<'s> {
-    let mut args = args.deref();
-    let mut result = Value::Integer(0);
+/// Why a builtin has no result: the type of the error it signals, as in
+/// `handler-case`, and its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault {
+    pub kind: &'static str,
+    pub message: &'static str,
+}
+
+impl From<Failure> for Fault {
+    fn from(failure: Failure) -> Self {
+        Fault { kind: failure.kind(), message: failure.message() }
+    }
+}
+
+const TOO_FEW_ARGUMENTS: Fault = Fault { kind: "program-error", message: "too few arguments" };
+pub(crate) const NOT_A_NUMBER: Fault = Fault { kind: "type-error", message: "not a number" };
+const NOT_A_RATIONAL: Fault = Fault { kind: "type-error", message: "not a rational number" };
+pub(crate) const NOT_AN_INTEGER: Fault = Fault { kind: "type-error", message: "not an integer" };
+pub(crate) const NOT_A_SYMBOL: Fault = Fault { kind: "type-error", message: "not a symbol" };
+const NOT_A_STRING: Fault = Fault { kind: "type-error", message: "not a string" };
+const NOT_A_CHARACTER: Fault = Fault { kind: "type-error", message: "not a character" };
+const NOT_A_LIST: Fault = Fault { kind: "type-error", message: "not a list" };
+const NOT_A_VECTOR: Fault = Fault { kind: "type-error", message: "not a vector" };
+const NOT_A_TABLE: Fault = Fault { kind: "type-error", message: "not a hash table" };
+const NOT_BYTES: Fault = Fault { kind: "type-error", message: "not a byte buffer" };
+const NOT_A_BYTE: Fault = Fault { kind: "type-error", message: "not a byte" };
+const NOT_A_FIELD: Fault = Fault { kind: "type-error", message: "not a field name" };
+const NO_TEXT: Fault = Fault { kind: "type-error", message: "has no text form" };
+const OUT_OF_RANGE: Fault = Fault { kind: "type-error", message: "out of range" };
+const TABLE_FULL: Fault = Fault { kind: "storage-condition", message: "hash table is full" };
+const NO_ROOM: Fault = Fault { kind: "storage-condition", message: "no room" };
+const UNREADABLE: Fault = Fault { kind: "parse-error", message: "unreadable input" };
+
+/// The first `K` elements of `args` and the list after them, or `None` if
+/// there are fewer.
+fn arguments<'a, 's, const K: usize>(args: &'a Value<'s>) -> Option<([&'a RcValue<'s>; K], &'a Value<'s>)> {
+    let mut before = [None; K];
+    let mut args = args;
+    for arg in before.iter_mut() {
+        let Value::Cons(car, cdr) = args else { return None };
+        *arg = Some(car);
+        args = cdr;
+    }
+
+    Some((before.map(Option::unwrap), args))
+}
+
+fn number(value: &Value) -> Result<Number, Fault> {
+    Number::from_value(value).ok_or(NOT_A_NUMBER)
+}
+
+/// Folds `op` over the numbers in `args`, starting from the first of two or
+/// more, or from `identity` when there are fewer, so that `(- x)` is
+/// `(- 0 x)` and `(/ x)` is `(/ 1 x)`.
+fn arithmetic<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, identity: Number, op: Op) -> Result<RcValue<'s>, Fault> {
+    let (mut result, mut args) = match args {
+        Value::Cons(car, cdr) if matches!(cdr.deref(), Value::Cons(..)) => (number(car)?, cdr.deref()),
+        args => (identity, args),
+    };
 
     loop {
         match args {
             Value::Cons(car, cdr) => {
-                result = match (car.deref(), result) {
-                    (Value::Integer(car), Value::Integer(n)) => {
-                        Value::Integer(car + n)
-                    },
-                    (Value::Number(car), Value::Integer(n)) => {
-                        Value::Number(car + n as f64)
-                    },
-                    (Value::Integer(car), Value::Number(n)) => {
-                        Value::Number(*car as f64 + n)
-                    },
-                    (Value::Number(car), Value::Number(n)) => {
-                        Value::Number(car + n)
-                    }
-                    _ => {
-                        panic!()
-                    }
-                };
-
-                args = &cdr;
+                result = result.combine(number(car)?, op)?;
+                args = cdr;
             },
-            Value::Symbol("nil") => { return match result {
-                Value::Integer(result) => pool.new_integer(result),
-                Value::Number(result) => pool.new_number(result),
-                _ => panic!()
-            }},
-            _ => {
+            Value::Symbol("nil") => return Ok(result.into_value(pool)),
+            _ => return Ok(pool.new_symbol("nil")),
+        }
+    }
+}
+
+pub fn add<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(arithmetic(env.pool, &args, Number::Integer(0), Op::Add))
+}
+
+pub fn sub<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(arithmetic(env.pool, &args, Number::Integer(0), Op::Sub))
+}
+
+pub fn times<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(arithmetic(env.pool, &args, Number::Integer(1), Op::Mul))
+}
+
+pub fn div<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(arithmetic(env.pool, &args, Number::Integer(1), Op::Div))
+}
+
+/// Whether each number in `args` stands in `holds` to the next, so that
+/// `(< a b c)` is `a < b` and `b < c`. A NaN is unordered with everything.
+fn comparison<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, holds: fn(Ordering) -> bool) -> Result<RcValue<'s>, Fault> {
+    let Value::Cons(first, args) = args else { return Ok(pool.new_symbol("t")) };
+    let mut previous = number(first)?;
+    let mut args = args.deref();
+    while let Value::Cons(car, cdr) = args {
+        let next = number(car)?;
+        if !previous.compare(&next).is_some_and(holds) {
+            return Ok(pool.new_symbol("nil"));
+        }
+        previous = next;
+        args = cdr;
+    }
+
+    Ok(pool.new_symbol("t"))
+}
+
+pub fn equal<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(comparison(env.pool, &args, Ordering::is_eq))
+}
+
+pub fn less<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(comparison(env.pool, &args, Ordering::is_lt))
+}
+
+pub fn greater<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(comparison(env.pool, &args, Ordering::is_gt))
+}
+
+pub fn less_or_equal<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(comparison(env.pool, &args, Ordering::is_le))
+}
+
+pub fn greater_or_equal<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(comparison(env.pool, &args, Ordering::is_ge))
+}
+
+/// Whether no two numbers in `args` are equal.
+pub fn not_equal<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+
+    let mut args = args.deref();
+    while let Value::Cons(car, cdr) = args {
+        let Ok(a) = number(car) else { return env.fail(NOT_A_NUMBER) };
+        let mut rest = cdr.deref();
+        while let Value::Cons(car, cdr) = rest {
+            let Ok(b) = number(car) else { return env.fail(NOT_A_NUMBER) };
+            if a.compare(&b) == Some(Ordering::Equal) {
                 return pool.new_symbol("nil");
             }
+            rest = cdr;
         }
+        args = cdr;
     }
+
+    pool.new_symbol("t")
+}
+
+/// Applies `f` to the single number in `args`.
+fn conversion<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, f: fn(&Number) -> Result<Number, Fault>) -> Result<RcValue<'s>, Fault> {
+    if let Value::Cons(car, _) = args {
+        return Ok(f(&number(car)?)?.into_value(pool));
+    }
+
+    Ok(pool.new_symbol("nil"))
+}
+
+pub fn numerator<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| number.numerator().ok_or(NOT_A_RATIONAL)))
+}
+
+pub fn denominator<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| number.denominator().ok_or(NOT_A_RATIONAL)))
 }
 
-pub fn sub<'s, Context, const N: usize>(_: &mut Context, pool: &'s Pool<'s, N>, args: RcValue<'s>) -> RcValue<'s> {
+pub fn floor<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.floor()?)))
+}
+
+pub fn round<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.round()?)))
+}
+
+pub fn ceiling<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.ceiling()?)))
+}
+
+pub fn truncate<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.truncate()?)))
+}
+
+pub fn float<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.to_float())))
+}
+
+/// Like `truncate`: `(integer 2.7)` is `2` and `(integer -7/2)` is `-3`.
+pub fn integer<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.truncate()?)))
+}
+
+pub fn abs<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(conversion(env.pool, &args, |number| Ok(number.clone().abs()?)))
+}
+
+/// Applies `f` to the two numbers in `args`.
+fn binary<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, f: fn(Number, Number) -> Result<Number, Failure>) -> Result<RcValue<'s>, Fault> {
+    let ([a, b], _) = arguments(args).ok_or(TOO_FEW_ARGUMENTS)?;
+
+    Ok(f(number(a)?, number(b)?)?.into_value(pool))
+}
+
+pub fn modulo<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(binary(env.pool, &args, Number::modulo))
+}
+
+pub fn remainder<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(binary(env.pool, &args, Number::remainder))
+}
+
+pub fn expt<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(binary(env.pool, &args, Number::expt))
+}
+
+/// The argument in `args` that stands in `wanted` to all the others,
+/// unconverted; the first of those that are equal.
+fn extremum<'s>(args: &RcValue<'s>, wanted: Ordering) -> Result<RcValue<'s>, Fault> {
+    let Value::Cons(first, rest) = args.deref() else { return Err(TOO_FEW_ARGUMENTS) };
+    let (mut result, mut best) = (first, number(first)?);
+    let mut rest = rest.deref();
+    while let Value::Cons(car, cdr) = rest {
+        let candidate = number(car)?;
+        if candidate.compare(&best) == Some(wanted) {
+            (result, best) = (car, candidate);
+        }
+        rest = cdr;
+    }
+
+    Ok(result.clone())
+}
+
+pub fn min<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(extremum(&args, Ordering::Less))
+}
+
+pub fn max<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(extremum(&args, Ordering::Greater))
+}
+
+/// Applies the `libm` function `f` to the single number in `args` as a float.
+fn math<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, f: fn(f64) -> f64) -> Result<RcValue<'s>, Fault> {
+    if let Value::Cons(car, _) = args {
+        return Ok(pool.new_number(f(number(car)?.to_f64())));
+    }
+
+    Ok(pool.new_symbol("nil"))
+}
+
+pub fn sqrt<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::sqrt))
+}
+
+pub fn exp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::exp))
+}
+
+pub fn sin<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::sin))
+}
+
+pub fn cos<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::cos))
+}
+
+pub fn tan<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::tan))
+}
+
+pub fn asin<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::asin))
+}
+
+pub fn acos<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(math(env.pool, &args, libm::acos))
+}
+
+/// `(log x)` is the natural logarithm of `x`, and `(log x b)` its logarithm
+/// to base `b`.
+pub fn log<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+
     match args.deref() {
-        Value::Cons(car, args) => {
-            if let Value::Symbol("nil") = args.deref() {
-                match car.deref() {
-                    Value::Integer(n) => {
-                        return pool.new_integer(-n);
-                    },
-                    Value::Number(x) => {
-                        return pool.new_number(-x);
-                    },
-                    _ => {
-                        return pool.new_symbol("nil");
-                    }
-                }
-            }
+        Value::Cons(x, rest) if matches!(rest.deref(), Value::Cons(..)) => {
+            let Value::Cons(base, _) = rest.deref() else { unreachable!() };
+            let (Ok(x), Ok(base)) = (number(x), number(base)) else { return env.fail(NOT_A_NUMBER) };
+            pool.new_number(libm::log(x.to_f64()) / libm::log(base.to_f64()))
+        },
+        args => env.or_fail(math(pool, args, libm::log)),
+    }
+}
 
-            let mut result = match car.deref() {
-                Value::Integer(n) =>  Value::Integer(*n),
-                Value::Number(x) =>  Value::Number(*x),
-                _ => panic!()
-            };
-            let mut args = args.deref();
-
-            loop {
-                match args {
-                    Value::Cons(car, cdr) => {
-                        result = match (car.deref(), result) {
-                            (Value::Integer(car), Value::Integer(n)) => {
-                                Value::Integer(n - car)
-                            },
-                            (Value::Number(car), Value::Integer(n)) => {
-                                Value::Number(n as f64 - car)
-                            },
-                            (Value::Integer(car), Value::Number(n)) => {
-                                Value::Number(n - *car as f64)
-                            },
-                            (Value::Number(car), Value::Number(n)) => {
-                                Value::Number(n - car)
-                            }
-                            _ => {
-                                panic!()
-                            }
-                        };
-
-                        args = &cdr;
-                    },
-                    Value::Symbol("nil") => { return match result {
-                        Value::Integer(result) => pool.new_integer(result),
-                        Value::Number(result) => pool.new_number(result),
-                        _ => panic!()
-                    }},
-                    _ => {
-                        return pool.new_symbol("nil");
-                    }
-                }
-            }
+/// `(atan y)` is the arc tangent of `y`, and `(atan y x)` that of `y / x`
+/// in the quadrant of the point `(x, y)`.
+pub fn atan<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+
+    match args.deref() {
+        Value::Cons(y, rest) if matches!(rest.deref(), Value::Cons(..)) => {
+            let Value::Cons(x, _) = rest.deref() else { unreachable!() };
+            let (Ok(y), Ok(x)) = (number(y), number(x)) else { return env.fail(NOT_A_NUMBER) };
+            pool.new_number(libm::atan2(y.to_f64(), x.to_f64()))
         },
-        _ => pool.new_integer(0)
+        args => env.or_fail(math(pool, args, libm::atan)),
     }
 }
 
-pub fn times<'s, Context, const N: usize>(_: &mut Context, pool: &'s Pool<'s, N>, args: RcValue<'s>) -> RcValue<'s> {
-    let mut args = args.deref();
-    let mut result = Value::Integer(1);
+/// The first `K` elements of `args`, each of which must be an integer.
+fn integers<const K: usize>(args: &Value) -> Result<[i64; K], Fault> {
+    let (values, _) = arguments::<K>(args).ok_or(TOO_FEW_ARGUMENTS)?;
+    let mut result = [0; K];
+    for (n, value) in result.iter_mut().zip(values) {
+        let Value::Integer(value) = value.deref() else { return Err(NOT_AN_INTEGER) };
+        *n = *value;
+    }
 
-    loop {
-        match args {
-            Value::Cons(car, cdr) => {
-                result = match (car.deref(), result) {
-                    (Value::Integer(car), Value::Integer(n)) => {
-                        Value::Integer(car * n)
-                    },
-                    (Value::Number(car), Value::Integer(n)) => {
-                        Value::Number(car * n as f64)
-                    },
-                    (Value::Integer(car), Value::Number(n)) => {
-                        Value::Number(*car as f64 * n)
-                    },
-                    (Value::Number(car), Value::Number(n)) => {
-                        Value::Number(car * n)
-                    }
-                    _ => {
-                        panic!()
-                    }
-                };
-
-                args = &cdr;
+    Ok(result)
+}
+
+/// Folds `op` over the integers in `args`, starting from `identity`.
+fn bitwise<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, identity: i64, op: fn(i64, i64) -> i64) -> Result<RcValue<'s>, Fault> {
+    let mut result = identity;
+    let mut args = args;
+    while let Value::Cons(car, cdr) = args {
+        let Value::Integer(n) = car.deref() else { return Err(NOT_AN_INTEGER) };
+        result = op(result, *n);
+        args = cdr;
+    }
+
+    Ok(pool.new_integer(result))
+}
+
+pub fn logand<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(bitwise(env.pool, &args, -1, |a, b| a & b))
+}
+
+pub fn logior<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(bitwise(env.pool, &args, 0, |a, b| a | b))
+}
+
+pub fn logxor<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(bitwise(env.pool, &args, 0, |a, b| a ^ b))
+}
+
+pub fn lognot<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).map(|[n]| pool.new_integer(!n)))
+}
+
+/// `(ash n count)` shifts `n` left by `count` bits, or right by `-count`
+/// with the sign copied in, as `Number::ash` does. Shifting right by 64 or
+/// more leaves `0` or `-1`.
+pub fn ash<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).and_then(|[n, count]| Ok(Number::ash(n, count)?.into_value(pool))))
+}
+
+/// `(lsh n count)` shifts the 64 bits of `n` left by `count`, or right by
+/// `-count` with zeros shifted in, dropping the bits that fall off either
+/// end, as a register would. Shifting by 64 or more either way leaves `0`.
+pub fn lsh<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).map(|[n, count]| {
+        let bits = n as u64;
+        let shifted = match count {
+            64.. | ..=-64 => 0,
+            0.. => bits << count,
+            _ => bits >> -count,
+        };
+
+        pool.new_integer(shifted as i64)
+    }))
+}
+
+/// `(logbitp index n)`: whether bit `index` of `n` is set, counting from
+/// the least significant bit. Bits from 64 up are copies of the sign bit.
+pub fn logbitp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).and_then(|[index, n]| {
+        if index < 0 {
+            return Err(OUT_OF_RANGE);
+        }
+
+        Ok(pool.new_symbol(if (n >> index.min(63)) & 1 == 1 { "t" } else { "nil" }))
+    }))
+}
+
+/// The number of bits of `n` that differ from its sign bit: the one bits of
+/// a positive number, and the zero bits of a negative one.
+pub fn logcount<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).map(|[n]| {
+        let count = if n < 0 { (!n).count_ones() } else { n.count_ones() };
+        pool.new_integer(count as i64)
+    }))
+}
+
+/// The mask of a field `width` bits wide, all 64 bits for widths of 64 or more.
+fn field_mask(width: i64) -> Result<i64, Fault> {
+    match width {
+        ..=-1 => Err(OUT_OF_RANGE),
+        64.. => Ok(-1),
+        _ => Ok(((1u64 << width) - 1) as i64),
+    }
+}
+
+/// `(bit-extract n position width)`: the `width` bits of `n` from bit
+/// `position` up, shifted down to bit 0. Bits from 64 up are copies of the
+/// sign bit; a field 64 or more bits wide is returned as all 64 bits, which
+/// may be negative.
+pub fn bit_extract<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).and_then(|[n, position, width]| {
+        if position < 0 {
+            return Err(OUT_OF_RANGE);
+        }
+
+        Ok(pool.new_integer((n >> position.min(63)) & field_mask(width)?))
+    }))
+}
+
+/// `(bit-insert n position width bits)`: `n` with its `width` bits from bit
+/// `position` up replaced by the low bits of `bits`. Bits that would land
+/// at 64 or above are dropped.
+pub fn bit_insert<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(integers(&args).and_then(|[n, position, width, bits]| {
+        if position < 0 {
+            return Err(OUT_OF_RANGE);
+        }
+        if position >= 64 {
+            return Ok(pool.new_integer(n));
+        }
+
+        let mask = field_mask(width)? << position;
+        Ok(pool.new_integer(n & !mask | (bits << position) & mask))
+    }))
+}
+
+pub fn room<'s, Context>(env: &mut Env<'_, 's, Context>, _: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let stats = pool.stats();
+    let fields = [
+        ("capacity", stats.capacity),
+        ("live", stats.live),
+        ("free", stats.free),
+        ("high-water", stats.high_water),
+        ("allocations", stats.allocations),
+        ("failed-allocations", stats.failed_allocations),
+        ("arena-capacity", stats.arena_capacity),
+        ("arena-used", stats.arena_used),
+    ];
+
+    let mut result = pool.new_symbol("nil");
+    for (kind, count) in KINDS.iter().zip(stats.kinds.iter()).rev() {
+        let entry = pool.new_cons(pool.new_symbol(kind), pool.new_integer(*count as i64));
+        result = pool.new_cons(entry, result);
+    }
+    for (field, count) in fields.iter().rev() {
+        let entry = pool.new_cons(pool.new_symbol(field), pool.new_integer(*count as i64));
+        result = pool.new_cons(entry, result);
+    }
+
+    result
+}
+
+pub fn concat<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let mut len = 0;
+    let mut rest = args.deref();
+    while let Value::Cons(car, cdr) = rest {
+        match car.deref() {
+            Value::String(text) => len += text.len(),
+            _ => return env.fail(NOT_A_STRING),
+        }
+        rest = cdr;
+    }
+
+    let Some(mut block) = pool.alloc_block(len) else { return env.fail(NO_ROOM) };
+    let mut bytes = block.bytes_mut();
+    let mut rest = args.deref();
+    while let Value::Cons(car, cdr) = rest {
+        if let Value::String(text) = car.deref() {
+            let (head, tail) = bytes.split_at_mut(text.len());
+            head.copy_from_slice(text.as_bytes());
+            bytes = tail;
+        }
+        rest = cdr;
+    }
+
+    match pool.alloc(Value::String(unsafe { Text::from_utf8_unchecked(block) })) {
+        Ok(string) => string,
+        Err(_) => env.fail(NO_ROOM),
+    }
+}
+
+pub fn string<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    if let Value::Cons(car, _) = args.deref() {
+        let mut buffer: heapless::String<48> = heapless::String::new();
+        match car.deref() {
+            Value::Integer(n) => write!(buffer, "{}", n).unwrap(),
+            Value::Number(x) => write!(buffer, "{:?}", x).unwrap(),
+            Value::Ratio(n, d) => write!(buffer, "{}/{}", n, d).unwrap(),
+            Value::Char(c) => buffer.push(*c).unwrap(),
+            Value::Symbol(symbol) => return pool.new_string(symbol).unwrap_or_else(|| env.fail(NO_ROOM)),
+            Value::Named(name) => return pool.new_string(name.as_str()).unwrap_or_else(|| env.fail(NO_ROOM)),
+            Value::Gensym(n) => write!(buffer, "#:g{}", n).unwrap(),
+            Value::String(_) => return car.clone(),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(n) => {
+                return pool.new_string(&alloc::string::ToString::to_string(n)).unwrap_or_else(|| env.fail(NO_ROOM));
             },
-            Value::Symbol("nil") => { return match result {
-                Value::Integer(result) => pool.new_integer(result),
-                Value::Number(result) => pool.new_number(result),
-                _ => panic!()
-            }},
-            _ => {
-                return pool.new_symbol("nil");
-            }
+            _ => return env.fail(NO_TEXT),
         }
+
+        return pool.new_string(&buffer).unwrap_or_else(|| env.fail(NO_ROOM));
     }
+
+    pool.new_symbol("nil")
 }
 
-pub fn div<'s, Context, const N: usize>(_: &mut Context, pool: &'s Pool<'s, N>, args: RcValue<'s>) -> RcValue<'s> {
-    match args.deref() {
-        Value::Cons(car, args) => {
-            if let Value::Symbol("nil") = args.deref() {
-                match car.deref() {
-                    Value::Integer(_) => {
-                        return pool.new_integer(0);
-                    },
-                    Value::Number(x) => {
-                        return pool.new_number(1.0/x);
-                    },
-                    _ => {
-                        return pool.new_symbol("nil");
-                    }
-                }
-            }
+pub fn string_length<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    if let Value::Cons(car, _) = args.deref() {
+        if let Value::String(text) = car.deref() {
+            return pool.new_integer(text.chars().count() as i64);
+        }
+    }
 
-            let mut result = match car.deref() {
-                Value::Integer(n) =>  Value::Integer(*n),
-                Value::Number(x) =>  Value::Number(*x),
-                _ => panic!()
-            };
-            let mut args = args.deref();
-
-            loop {
-                match args {
-                    Value::Cons(car, cdr) => {
-                        result = match (car.deref(), result) {
-                            (Value::Integer(car), Value::Integer(n)) => {
-                                Value::Integer(n / car)
-                            },
-                            (Value::Number(car), Value::Integer(n)) => {
-                                Value::Number(n as f64 / car)
-                            },
-                            (Value::Integer(car), Value::Number(n)) => {
-                                Value::Number(n / *car as f64)
-                            },
-                            (Value::Number(car), Value::Number(n)) => {
-                                Value::Number(n / car)
-                            }
-                            _ => {
-                                panic!()
-                            }
-                        };
-
-                        args = &cdr;
-                    },
-                    Value::Symbol("nil") => { return match result {
-                        Value::Integer(result) => pool.new_integer(result),
-                        Value::Number(result) => pool.new_number(result),
-                        _ => panic!()
-                    }},
-                    _ => {
-                        return pool.new_symbol("nil");
-                    }
-                }
-            }
+    pool.new_symbol("nil")
+}
+
+/// `(string-ref string index)`: the character at `index`, counting in
+/// characters from zero, or nil past the end.
+pub fn string_ref<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Some(([string, index], _)) = arguments(&args) else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::String(text) = string.deref() else { return env.fail(NOT_A_STRING) };
+    let Value::Integer(index) = index.deref() else { return env.fail(NOT_AN_INTEGER) };
+
+    match usize::try_from(*index).ok().and_then(|index| text.chars().nth(index)) {
+        Some(c) => pool.new_char(c),
+        None => pool.new_symbol("nil"),
+    }
+}
+
+pub fn char_to_integer<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(car, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Char(c) = car.deref() else { return env.fail(NOT_A_CHARACTER) };
+
+    env.pool.new_integer(*c as i64)
+}
+
+/// The character with the code given, or nil if there is none.
+pub fn integer_to_char<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Value::Cons(car, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Integer(n) = car.deref() else { return env.fail(NOT_AN_INTEGER) };
+
+    match u32::try_from(*n).ok().and_then(char::from_u32) {
+        Some(c) => pool.new_char(c),
+        None => pool.new_symbol("nil"),
+    }
+}
+
+/// The upper case of a character, or the character itself if it has none
+/// that is a single character.
+pub fn char_upcase<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(car, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Char(c) = car.deref() else { return env.fail(NOT_A_CHARACTER) };
+
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => env.pool.new_char(upper),
+        _ => car.clone(),
+    }
+}
+
+pub fn char_alphabetic_p<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(car, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Char(c) = car.deref() else { return env.fail(NOT_A_CHARACTER) };
+
+    env.pool.new_symbol(if c.is_alphabetic() { "t" } else { "nil" })
+}
+
+/// A vector of the elements of `list`, or `None` if there is no room for it.
+pub(crate) fn vector_from_list<'s>(pool: &'s dyn Heap<'s>, list: &RcValue<'s>) -> Option<RcValue<'s>> {
+    let mut len = 0;
+    let mut rest = list.deref();
+    while let Value::Cons(_, cdr) = rest {
+        len += 1;
+        rest = cdr;
+    }
+
+    let block = pool.alloc_block(Vector::size(len)?)?;
+    let mut rest = list.clone();
+    let vector = Vector::from_fn(block, |_| {
+        let Value::Cons(car, cdr) = rest.deref() else { unreachable!() };
+        let car = car.clone();
+        rest = cdr.clone();
+        car
+    });
+
+    pool.alloc(Value::Vector(vector)).ok()
+}
+
+/// `(make-vector len fill)`: a vector of `len` elements, each `fill`, or nil
+/// if that is left out.
+pub fn make_vector<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Value::Cons(len, rest) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Integer(len) = len.deref() else { return env.fail(NOT_AN_INTEGER) };
+    let Ok(len) = usize::try_from(*len) else { return env.fail(OUT_OF_RANGE) };
+    let fill = match rest.deref() {
+        Value::Cons(fill, _) => fill.clone(),
+        _ => pool.new_symbol("nil"),
+    };
+
+    pool.new_vector(len, &mut |_| fill.clone()).unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+pub fn vector<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    vector_from_list(env.pool, &args).unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+/// `(vector-ref vector index)`: the element at `index`, or nil past the end.
+pub fn vector_ref<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Some(([vector, index], _)) = arguments(&args) else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Vector(vector) = vector.deref() else { return env.fail(NOT_A_VECTOR) };
+    let Value::Integer(index) = index.deref() else { return env.fail(NOT_AN_INTEGER) };
+
+    usize::try_from(*index).ok()
+        .and_then(|index| vector.get(index))
+        .unwrap_or_else(|| pool.new_symbol("nil"))
+}
+
+/// `(vector-set! vector index value)` replaces the element at `index`,
+/// which must be within the vector, and returns `value`.
+pub fn vector_set<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Some(([vector, index, value], _)) = arguments(&args) else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Vector(vector) = vector.deref() else { return env.fail(NOT_A_VECTOR) };
+    let Value::Integer(index) = index.deref() else { return env.fail(NOT_AN_INTEGER) };
+    let index = usize::try_from(*index).unwrap_or(usize::MAX);
+    if vector.set(index, value.clone()).is_err() {
+        return env.fail(OUT_OF_RANGE);
+    }
+
+    value.clone()
+}
+
+pub fn vector_length<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(vector, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Vector(vector) = vector.deref() else { return env.fail(NOT_A_VECTOR) };
+
+    env.pool.new_integer(vector.len() as i64)
+}
+
+pub fn vector_to_list<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Value::Cons(vector, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Vector(vector) = vector.deref() else { return env.fail(NOT_A_VECTOR) };
+
+    let mut list = pool.new_symbol("nil");
+    for index in (0..vector.len()).rev() {
+        list = pool.new_cons(vector.get(index).unwrap(), list);
+    }
+
+    list
+}
+
+pub fn list_to_vector<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(list, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    if !matches!(list.deref(), Value::Cons(..) | Value::Symbol("nil")) {
+        return env.fail(NOT_A_LIST);
+    }
+
+    vector_from_list(env.pool, list).unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+/// Entries in a hash table made without a capacity, to begin with.
+pub const DEFAULT_TABLE_CAPACITY: usize = 16;
+
+/// `(make-hash-table capacity)`: an empty table that holds up to `capacity`
+/// entries. Without a capacity, the table starts at
+/// `DEFAULT_TABLE_CAPACITY` and, with the `alloc` feature, grows as needed;
+/// without `alloc` it stays at that size.
+pub fn make_hash_table<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let table = match args.deref() {
+        Value::Cons(capacity, _) => {
+            let Value::Integer(capacity) = capacity.deref() else { return env.fail(NOT_AN_INTEGER) };
+            let Ok(capacity) = usize::try_from(*capacity) else { return env.fail(OUT_OF_RANGE) };
+            pool.new_table(capacity, false)
         },
-        _ => pool.new_integer(0)
+        _ => pool.new_table(DEFAULT_TABLE_CAPACITY, cfg!(feature = "alloc")),
+    };
+
+    table.unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+/// The table and the arguments before it, which `gethash`, `puthash` and
+/// the rest take last, as in `(gethash key table)`.
+fn table_args<'a, 's, const K: usize>(args: &'a Value<'s>) -> Result<([&'a RcValue<'s>; K], &'a Table<'s>, &'a Value<'s>), Fault> {
+    let (before, args) = arguments(args).ok_or(TOO_FEW_ARGUMENTS)?;
+    let Value::Cons(table, rest) = args else { return Err(TOO_FEW_ARGUMENTS) };
+    let Value::Table(table) = table.deref() else { return Err(NOT_A_TABLE) };
+
+    Ok((before, table, rest))
+}
+
+/// `(gethash key table default)`: what `key` is bound to in `table`, or
+/// `default`, or nil if that is left out.
+pub fn gethash<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let ([key], table, rest) = match table_args(&args) {
+        Ok(args) => args,
+        Err(fault) => return env.fail(fault),
+    };
+
+    table.get(key).unwrap_or_else(|| match rest {
+        Value::Cons(default, _) => default.clone(),
+        _ => pool.new_symbol("nil"),
+    })
+}
+
+/// `(puthash key value table)` binds `key` to `value`, growing the table if
+/// it is full and growable, and returns `value`. A full table that cannot
+/// grow, or has no room to, signals an error.
+pub fn puthash<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let ([key, value], table, _) = match table_args(&args) {
+        Ok(args) => args,
+        Err(fault) => return env.fail(fault),
+    };
+
+    let mut entry = (key.clone(), value.clone());
+    while let Err((key, value)) = table.insert(entry.0, entry.1) {
+        if !table.is_growable() {
+            return env.fail(TABLE_FULL);
+        }
+
+        let capacity = table.capacity().saturating_mul(2);
+        let Some(block) = Table::size(capacity).and_then(|size| pool.alloc_block(size)) else {
+            return env.fail(TABLE_FULL);
+        };
+        table.grow(block, capacity);
+        entry = (key, value);
     }
+
+    value.clone()
+}
+
+/// `(remhash key table)` unbinds `key`, returning whether it was bound.
+pub fn remhash<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let ([key], table, _) = match table_args(&args) {
+        Ok(args) => args,
+        Err(fault) => return env.fail(fault),
+    };
+
+    env.pool.new_symbol(if table.remove(key).is_some() { "t" } else { "nil" })
+}
+
+/// `(maphash f table)` calls the function named `f` with each key and value.
+pub fn maphash<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let ([f], table, _) = match table_args(&args) {
+        Ok(args) => args,
+        Err(fault) => return env.fail(fault),
+    };
+    let Some(_) = f.name() else { return env.fail(NOT_A_SYMBOL) };
+
+    let nil = pool.new_symbol("nil");
+    let quote = |value| pool.new_cons(pool.new_symbol("quote"), pool.new_cons(value, nil.clone()));
+    table.for_each(|key, value| {
+        let args = pool.new_cons(quote(key), pool.new_cons(quote(value), nil.clone()));
+        if !env.unwinding() {
+            env.eval(pool.new_cons(f.clone(), args));
+        }
+    });
+
+    nil
 }
 
-pub type Builtin<'s, Context, const N: usize> = fn(context: &mut Context, pool: &'s Pool<'s, N>, list: RcValue<'s>) -> RcValue<'s>;
+pub fn hash_table_count<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(table, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Table(table) = table.deref() else { return env.fail(NOT_A_TABLE) };
 
-pub struct Builtins<'s, Context, const N: usize, const BUILTINS: usize> {
-    map: FnvIndexMap<&'s str, Builtin<'s, Context, N>, BUILTINS>,
+    env.pool.new_integer(table.len() as i64)
 }
 
-impl <'s, Context, const N: usize, const BUILTINS: usize> Builtins<'s, Context, N, BUILTINS> {
+/// The byte buffer and the `K` arguments after it, which `byte-ref` and the
+/// rest take first, as in `(byte-ref buffer index)`.
+fn bytes_args<'a, 's, const K: usize>(args: &'a Value<'s>) -> Result<(&'a Bytes<'s>, [&'a RcValue<'s>; K], &'a Value<'s>), Fault> {
+    let Value::Cons(buffer, rest) = args else { return Err(TOO_FEW_ARGUMENTS) };
+    let (after, args) = arguments(rest).ok_or(TOO_FEW_ARGUMENTS)?;
+    let Value::Bytes(buffer) = buffer.deref() else { return Err(NOT_BYTES) };
+
+    Ok((buffer, after, args))
+}
+
+/// An offset into a byte buffer; negative ones are past any end.
+fn offset(value: &Value) -> Result<usize, Fault> {
+    let Value::Integer(offset) = value else { return Err(NOT_AN_INTEGER) };
+
+    Ok(usize::try_from(*offset).unwrap_or(usize::MAX))
+}
+
+fn byte(value: &Value) -> Result<u8, Fault> {
+    let Value::Integer(byte) = value else { return Err(NOT_AN_INTEGER) };
+
+    u8::try_from(*byte).map_err(|_| NOT_A_BYTE)
+}
+
+/// `(make-bytes len fill)`: a byte buffer of `len` bytes, each `fill`, or
+/// zero if that is left out.
+pub fn make_bytes<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(len, rest) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::Integer(len) = len.deref() else { return env.fail(NOT_AN_INTEGER) };
+    let Ok(len) = usize::try_from(*len) else { return env.fail(OUT_OF_RANGE) };
+    let fill = match rest.deref() {
+        Value::Cons(fill, _) => byte(fill),
+        _ => Ok(0),
+    };
+
+    match fill {
+        Ok(fill) => env.pool.new_bytes(len, &mut |_| fill).unwrap_or_else(|| env.fail(NO_ROOM)),
+        Err(fault) => env.fail(fault),
+    }
+}
+
+/// `(bytes 1 2 255)`: a byte buffer of the given bytes.
+pub fn bytes<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let mut list = args.deref();
+    let mut len = 0;
+    while let Value::Cons(car, cdr) = list {
+        if let Err(fault) = byte(car) {
+            return env.fail(fault);
+        }
+        len += 1;
+        list = cdr;
+    }
+
+    let mut list = args.deref();
+    let buffer = env.pool.new_bytes(len, &mut |_| {
+        let Value::Cons(car, cdr) = list else { unreachable!() };
+        list = cdr;
+        byte(car).unwrap()
+    });
+
+    buffer.unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+/// `(byte-ref buffer index)`: the byte at `index`, or nil past the end.
+pub fn byte_ref<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(bytes_args(&args).and_then(|(buffer, [index], _)| {
+        Ok(match buffer.get(offset(index)?) {
+            Some(byte) => pool.new_integer(byte as i64),
+            None => pool.new_symbol("nil"),
+        })
+    }))
+}
+
+/// `(byte-set buffer index byte)` overwrites the byte at `index`, which must
+/// be within the buffer, and returns `byte`.
+pub fn byte_set<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(bytes_args(&args).and_then(|(buffer, [index, value], _)| {
+        if !buffer.write(offset(index)?, &[byte(value)?]) {
+            return Err(OUT_OF_RANGE);
+        }
+
+        Ok(value.clone())
+    }))
+}
+
+pub fn bytes_length<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(bytes_args(&args).map(|(buffer, [], _)| pool.new_integer(buffer.len() as i64)))
+}
+
+/// `(subbytes buffer start end)`: a new buffer holding a copy of the bytes
+/// from `start` up to `end`, or to the end of `buffer` if that is left out.
+pub fn subbytes<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    env.or_fail(bytes_args(&args).and_then(|(buffer, [start], rest)| {
+        let start = offset(start)?;
+        let end = match rest {
+            Value::Cons(end, _) => offset(end)?,
+            _ => buffer.len(),
+        };
+        if start > end || end > buffer.len() {
+            return Err(OUT_OF_RANGE);
+        }
+
+        pool.new_bytes(end - start, &mut |index| buffer.get(start + index).unwrap()).ok_or(NO_ROOM)
+    }))
+}
+
+/// `(bytes-concat a b ...)`: a new buffer holding the bytes of each buffer
+/// in turn.
+pub fn bytes_concat<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let mut list = args.deref();
+    let mut len = 0;
+    while let Value::Cons(buffer, cdr) = list {
+        let Value::Bytes(buffer) = buffer.deref() else { return env.fail(NOT_BYTES) };
+        len += buffer.len();
+        list = cdr;
+    }
+
+    let (mut list, mut start) = (args.deref(), 0);
+    let buffer = env.pool.new_bytes(len, &mut |index| loop {
+        let Value::Cons(buffer, cdr) = list else { unreachable!() };
+        let Value::Bytes(buffer) = buffer.deref() else { unreachable!() };
+        match buffer.get(index - start) {
+            Some(byte) => return byte,
+            None => (list, start) = (cdr, start + buffer.len()),
+        }
+    });
+
+    buffer.unwrap_or_else(|| env.fail(NO_ROOM))
+}
+
+/// How `bytes-read` and `bytes-write` lay out a number, named as in `u8`,
+/// `u16le` or `f32be`.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+/// The field named by `name`, its width in bytes, and whether it is
+/// big-endian.
+fn field(name: &Value) -> Result<(Field, usize, bool), Fault> {
+    match name.name() {
+        Some("u8") => Ok((Field::Unsigned, 1, false)),
+        Some("u16le") => Ok((Field::Unsigned, 2, false)),
+        Some("u16be") => Ok((Field::Unsigned, 2, true)),
+        Some("u32le") => Ok((Field::Unsigned, 4, false)),
+        Some("u32be") => Ok((Field::Unsigned, 4, true)),
+        Some("i32le") => Ok((Field::Signed, 4, false)),
+        Some("i32be") => Ok((Field::Signed, 4, true)),
+        Some("f32le") => Ok((Field::Float, 4, false)),
+        Some("f32be") => Ok((Field::Float, 4, true)),
+        _ => Err(NOT_A_FIELD),
+    }
+}
+
+/// `(bytes-read buffer offset 'u16le)`: the number laid out at `offset` as
+/// the field says, or nil if it would run past the end.
+pub fn bytes_read<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(read_field(env.pool, &args))
+}
+
+fn read_field<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>) -> Result<RcValue<'s>, Fault> {
+    let (buffer, [at, name], _) = bytes_args(args)?;
+    let (field, width, big_endian) = field(name)?;
+
+    let mut raw = [0; 4];
+    if !buffer.read(offset(at)?, &mut raw[4 - width..]) {
+        return Ok(pool.new_symbol("nil"));
+    }
+    if !big_endian {
+        raw[4 - width..].reverse();
+    }
+
+    let bits = u32::from_be_bytes(raw);
+    Ok(match field {
+        Field::Unsigned => pool.new_integer(bits as i64),
+        Field::Signed => pool.new_integer(bits as i32 as i64),
+        Field::Float => pool.new_number(f32::from_bits(bits) as f64),
+    })
+}
+
+/// `(bytes-write buffer offset 'u16le value)` lays out `value` at `offset`
+/// as the field says, and returns it. The field must be within the buffer,
+/// and an integer must fit it.
+pub fn bytes_write<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(write_field(&args))
+}
+
+fn write_field<'s>(args: &Value<'s>) -> Result<RcValue<'s>, Fault> {
+    let (buffer, [at, name, value], _) = bytes_args(args)?;
+    let (field, width, big_endian) = field(name)?;
+
+    let bits = match (field, value.deref()) {
+        (Field::Float, value) => (number(value)?.to_f64() as f32).to_bits(),
+        (Field::Signed, Value::Integer(n)) => i32::try_from(*n).map_err(|_| OUT_OF_RANGE)? as u32,
+        (Field::Unsigned, Value::Integer(n)) => match u32::try_from(*n) {
+            Ok(bits) if width == 4 || bits >> (8 * width) == 0 => bits,
+            _ => return Err(OUT_OF_RANGE),
+        },
+        _ => return Err(NOT_AN_INTEGER),
+    };
+
+    let mut raw = bits.to_be_bytes();
+    if !big_endian {
+        raw[4 - width..].reverse();
+    }
+    if !buffer.write(offset(at)?, &raw[4 - width..]) {
+        return Err(OUT_OF_RANGE);
+    }
+
+    Ok(value.clone())
+}
+
+/// `(type-of value)`: the symbol naming the kind of `value`, from
+/// `value::KINDS`.
+pub fn type_of<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(value, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+
+    env.pool.new_symbol(KINDS[value.kind()])
+}
+
+/// t if `holds` for the one argument, nil otherwise.
+fn predicate<'s>(pool: &'s dyn Heap<'s>, args: &Value<'s>, holds: fn(&Value<'s>) -> bool) -> Result<RcValue<'s>, Fault> {
+    let Value::Cons(value, _) = args else { return Err(TOO_FEW_ARGUMENTS) };
+
+    Ok(pool.new_symbol(if holds(value) { "t" } else { "nil" }))
+}
+
+pub fn integerp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| match value {
+        Value::Integer(_) => true,
+        #[cfg(feature = "bigint")]
+        Value::BigInt(_) => true,
+        _ => false,
+    }))
+}
+
+pub fn floatp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::Number(_))))
+}
+
+/// `(numberp value)`: whether `value` is an integer, ratio or float.
+pub fn numberp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| Number::from_value(value).is_some()))
+}
+
+pub fn symbolp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::Symbol(_) | Value::Named(_) | Value::Gensym(_))))
+}
+
+pub fn stringp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::String(_))))
+}
+
+pub fn consp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::Cons(_, _))))
+}
+
+/// `(listp value)`: whether `value` is a cons or nil.
+pub fn listp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::Cons(_, _) | Value::Symbol("nil"))))
+}
+
+/// `(atom value)`: whether `value` is anything but a cons.
+pub fn atom<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| !matches!(value, Value::Cons(_, _))))
+}
+
+pub fn null<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    env.or_fail(predicate(env.pool, &args, |value| matches!(value, Value::Symbol("nil"))))
+}
+
+/// `(functionp value)`: whether `value` is a symbol that can head a form
+/// and be called: one naming a builtin or a macro.
+pub fn functionp<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(value, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let callable = match Name::of(value) {
+        Some(Name::Text(name)) => env.builtins.builtin(name).is_some() || env.cells.get_macro(Name::Text(name)).is_some(),
+        Some(name) => env.cells.get_macro(name).is_some(),
+        None => false,
+    };
+
+    env.pool.new_symbol(if callable { "t" } else { "nil" })
+}
+
+/// `(error "message" data...)` signals an error, which unwinds to the
+/// nearest `handler-case` or `ignore-errors`. Its condition is the list
+/// `(type "message" data...)`, of type `simple-error` unless a symbol comes
+/// first, as in `(error 'bad-frame "short read" frame)`.
+pub fn error<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let condition = match args.deref() {
+        Value::Cons(kind, _) if kind.name().is_some() => args.clone(),
+        _ => pool.new_cons(pool.new_symbol("simple-error"), args.clone()),
+    };
+    env.unwind = Some(Unwind::Error(condition));
+
+    pool.new_symbol("nil")
+}
+
+/// `(throw tag value)` exits to the nearest `catch` of `tag`, which returns
+/// `value`, or nil if that is left out.
+pub fn throw<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let Value::Cons(tag, rest) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let value = match rest.deref() {
+        Value::Cons(value, _) => value.clone(),
+        _ => pool.new_symbol("nil"),
+    };
+    env.unwind = Some(Unwind::Throw(tag.clone(), value));
+
+    pool.new_symbol("nil")
+}
+
+/// `(break)` stops the innermost `while`.
+pub fn break_<'s, Context>(env: &mut Env<'_, 's, Context>, _: RcValue<'s>) -> RcValue<'s> {
+    env.unwind = Some(Unwind::Break);
+
+    env.pool.new_symbol("nil")
+}
+
+/// `(continue)` skips the rest of the innermost `while`'s body.
+pub fn continue_<'s, Context>(env: &mut Env<'_, 's, Context>, _: RcValue<'s>) -> RcValue<'s> {
+    env.unwind = Some(Unwind::Continue);
+
+    env.pool.new_symbol("nil")
+}
+
+pub fn eval<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    if let Value::Cons(car, _) = args.deref() {
+        return env.eval(car.clone());
+    }
+
+    env.pool.new_symbol("nil")
+}
+
+/// `(read-from-string "text")`: the first form written in `text`.
+pub fn read_from_string<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Value::Cons(text, _) = args.deref() else { return env.fail(TOO_FEW_ARGUMENTS) };
+    let Value::String(text) = text.deref() else { return env.fail(NOT_A_STRING) };
+
+    match parser::read(env.pool, text) {
+        Ok((_, datum)) => datum,
+        Err(_) => env.fail(UNREADABLE),
+    }
+}
+
+pub fn list<'s, Context>(_: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    args
+}
+
+pub fn cons<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let Some(([car, cdr], _)) = arguments(&args) else { return env.fail(TOO_FEW_ARGUMENTS) };
+
+    env.pool.new_cons(car.clone(), cdr.clone())
+}
+
+pub fn car<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    if let Value::Cons(list, _) = args.deref() {
+        match list.deref() {
+            Value::Cons(car, _) => return car.clone(),
+            Value::Symbol("nil") => return list.clone(),
+            _ => return env.fail(NOT_A_LIST),
+        }
+    }
+
+    env.pool.new_symbol("nil")
+}
+
+pub fn cdr<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    if let Value::Cons(list, _) = args.deref() {
+        match list.deref() {
+            Value::Cons(_, cdr) => return cdr.clone(),
+            Value::Symbol("nil") => return list.clone(),
+            _ => return env.fail(NOT_A_LIST),
+        }
+    }
+
+    env.pool.new_symbol("nil")
+}
+
+pub fn macroexpand_1<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    if let Value::Cons(form, _) = args.deref() {
+        return env.macroexpand_1(form).unwrap_or_else(|| form.clone());
+    }
+
+    env.pool.new_symbol("nil")
+}
+
+pub fn macroexpand<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    if let Value::Cons(form, _) = args.deref() {
+        let mut form = form.clone();
+        while let Some(expansion) = env.macroexpand_1(&form) {
+            form = expansion;
+        }
+
+        return form;
+    }
+
+    env.pool.new_symbol("nil")
+}
+
+/// A symbol no other symbol is equal to. It is numbered rather than named,
+/// so making one takes a cell but no text.
+pub fn gensym<'s, Context>(env: &mut Env<'_, 's, Context>, _: RcValue<'s>) -> RcValue<'s> {
+    let n = env.cells.gensym() as u64;
+
+    env.pool.alloc(Value::Gensym(n)).unwrap()
+}
+
+/// A builtin function. It sees the heap only as a `dyn Heap`, so the same
+/// pointer works with every pool, and can evaluate forms through its `Env`.
+pub type Builtin<'s, Context> = fn(env: &mut Env<'_, 's, Context>, list: RcValue<'s>) -> RcValue<'s>;
+
+pub struct Builtins<'s, Context, const BUILTINS: usize> {
+    map: FnvIndexMap<&'s str, Builtin<'s, Context>, BUILTINS>,
+}
+
+impl<'s, Context, const BUILTINS: usize> Default for Builtins<'s, Context, BUILTINS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s, Context, const BUILTINS: usize> Builtins<'s, Context, BUILTINS> {
+    /// The core builtins: arithmetic, comparison and rounding, strings,
+    /// characters, lists, exits, `eval` and macros, 40 in all, which fit a
+    /// `BUILTINS` of 64. The `with_` methods add the other
+    /// groups, which need room on top of these, as in
+    /// `Builtins::<_, 128>::new().with_math().with_vectors()`.
     pub fn new() -> Self {
         let map = FnvIndexMap::new();
         let mut this = Self { map };
@@ -218,17 +1233,162 @@ impl <'s, Context, const N: usize, const BUILTINS: usize> Builtins<'s, Context,
         this.add("-", sub);
         this.add("*", times);
         this.add("/", div);
+        this.add("=", equal);
+        this.add("/=", not_equal);
+        this.add("<", less);
+        this.add(">", greater);
+        this.add("<=", less_or_equal);
+        this.add(">=", greater_or_equal);
+        this.add("numerator", numerator);
+        this.add("denominator", denominator);
+        this.add("floor", floor);
+        this.add("round", round);
+        this.add("ceiling", ceiling);
+        this.add("truncate", truncate);
+        this.add("float", float);
+        this.add("integer", integer);
+        this.add("room", room);
+        this.add("concat", concat);
+        this.add("string", string);
+        this.add("string-length", string_length);
+        this.add("string-ref", string_ref);
+        this.add("char->integer", char_to_integer);
+        this.add("integer->char", integer_to_char);
+        this.add("char-upcase", char_upcase);
+        this.add("char-alphabetic-p", char_alphabetic_p);
+        this.add("eval", eval);
+        this.add("read-from-string", read_from_string);
+        this.add("list", list);
+        this.add("error", error);
+        this.add("throw", throw);
+        this.add("break", break_);
+        this.add("continue", continue_);
+        this.add("cons", cons);
+        this.add("car", car);
+        this.add("cdr", cdr);
+        this.add("macroexpand-1", macroexpand_1);
+        this.add("macroexpand", macroexpand);
+        this.add("gensym", gensym);
 
-        return this;
+        this
     }
 
-    pub fn add(&mut self, key: &'s str, builtin: Builtin<'s, Context, N>) {
-        if let Err(_) = self.map.insert(key, builtin) {
-            panic!()
+    /// Adds the 15 math builtins: `abs`, `mod`, `rem`, `expt`, `min` and
+    /// `max`, and the `libm` functions `sqrt` to `atan`.
+    pub fn with_math(mut self) -> Self {
+        self.add("abs", abs);
+        self.add("mod", modulo);
+        self.add("rem", remainder);
+        self.add("expt", expt);
+        self.add("min", min);
+        self.add("max", max);
+        self.add("sqrt", sqrt);
+        self.add("exp", exp);
+        self.add("log", log);
+        self.add("sin", sin);
+        self.add("cos", cos);
+        self.add("tan", tan);
+        self.add("asin", asin);
+        self.add("acos", acos);
+        self.add("atan", atan);
+
+        self
+    }
+
+    /// Adds the 11 type builtins, `type-of` and the predicates `integerp` to
+    /// `functionp`.
+    pub fn with_types(mut self) -> Self {
+        self.add("type-of", type_of);
+        self.add("integerp", integerp);
+        self.add("floatp", floatp);
+        self.add("numberp", numberp);
+        self.add("symbolp", symbolp);
+        self.add("stringp", stringp);
+        self.add("consp", consp);
+        self.add("listp", listp);
+        self.add("atom", atom);
+        self.add("null", null);
+        self.add("functionp", functionp);
+
+        self
+    }
+
+    /// Adds the 10 bitwise, shift and bit-field builtins, `logand` to
+    /// `bit-insert`.
+    pub fn with_bits(mut self) -> Self {
+        self.add("logand", logand);
+        self.add("logior", logior);
+        self.add("logxor", logxor);
+        self.add("lognot", lognot);
+        self.add("ash", ash);
+        self.add("lsh", lsh);
+        self.add("logbitp", logbitp);
+        self.add("logcount", logcount);
+        self.add("bit-extract", bit_extract);
+        self.add("bit-insert", bit_insert);
+
+        self
+    }
+
+    /// Adds the 7 vector builtins, `make-vector` to `list->vector`.
+    pub fn with_vectors(mut self) -> Self {
+        self.add("make-vector", make_vector);
+        self.add("vector", vector);
+        self.add("vector-ref", vector_ref);
+        self.add("vector-set!", vector_set);
+        self.add("vector-length", vector_length);
+        self.add("vector->list", vector_to_list);
+        self.add("list->vector", list_to_vector);
+
+        self
+    }
+
+    /// Adds the 6 hash table builtins, `make-hash-table` to
+    /// `hash-table-count`.
+    pub fn with_tables(mut self) -> Self {
+        self.add("make-hash-table", make_hash_table);
+        self.add("gethash", gethash);
+        self.add("puthash", puthash);
+        self.add("remhash", remhash);
+        self.add("maphash", maphash);
+        self.add("hash-table-count", hash_table_count);
+
+        self
+    }
+
+    /// Adds the 9 byte buffer builtins, `make-bytes` to `bytes-write`.
+    pub fn with_bytes(mut self) -> Self {
+        self.add("make-bytes", make_bytes);
+        self.add("bytes", bytes);
+        self.add("byte-ref", byte_ref);
+        self.add("byte-set", byte_set);
+        self.add("bytes-length", bytes_length);
+        self.add("subbytes", subbytes);
+        self.add("bytes-concat", bytes_concat);
+        self.add("bytes-read", bytes_read);
+        self.add("bytes-write", bytes_write);
+
+        self
+    }
+
+    pub fn add(&mut self, key: &'s str, builtin: Builtin<'s, Context>) {
+        if self.map.insert(key, builtin).is_err() {
+            panic!("no room for builtin {}: raise BUILTINS", key)
         }
     }
 
-    pub fn get(&self, key: &'_ str) -> Option<&Builtin<'s, Context, N>> {
+    pub fn get(&self, key: &'_ str) -> Option<&Builtin<'s, Context>> {
         self.map.get(key)
     }
 }
+
+/// A table of builtins as `eval` sees it, whatever its capacity.
+pub trait Library<'s, Context> {
+    fn builtin(&self, key: &str) -> Option<Builtin<'s, Context>>;
+}
+
+impl<'s, Context, const BUILTINS: usize> Library<'s, Context> for Builtins<'s, Context, BUILTINS> {
+    fn builtin(&self, key: &str) -> Option<Builtin<'s, Context>> {
+        self.get(key).copied()
+    }
+}
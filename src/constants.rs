@@ -1,15 +1,58 @@
-// use crate::value::Value;
+use crate::value::Value;
 
-// pub static PLUS: &'static str = "+";
-// pub static MINUS: &'static str = "-";
-// pub static NIL: &'static str = "nil";
-// pub static WHILE: &'static str = "while";
-// pub static QUOTE: &'static str = "quote";
+/// A `Value` that lives in static memory instead of a `Pool` cell.
+///
+/// Only variants without `RcValue` children are ever stored here, which is
+/// what makes sharing them between threads sound.
+#[repr(transparent)]
+pub struct Immediate(pub Value<'static>);
 
-// pub static PLUS_SYM: Value = Value::Symbol(PLUS);
-// pub static MINUS_SYM: Value = Value::Symbol(PLUS);
-// pub static NIL_SYM: Value = Value::Symbol(NIL);
-// pub static WHILE_SYM: Value = Value::Symbol(WHILE);
-// pub static QUOTE_SYM: Value = Value::Symbol(QUOTE);
+unsafe impl Sync for Immediate {}
 
-// pub static ZERO: Value = Value::Integer(0);
+pub static NIL: Immediate = Immediate(Value::Symbol("nil"));
+pub static T: Immediate = Immediate(Value::Symbol("t"));
+
+pub const SMALL_INTEGER_MIN: i64 = -128;
+pub const SMALL_INTEGER_MAX: i64 = 255;
+
+const SMALL_INTEGER_COUNT: usize = (SMALL_INTEGER_MAX - SMALL_INTEGER_MIN + 1) as usize;
+
+pub static SMALL_INTEGERS: [Immediate; SMALL_INTEGER_COUNT] = {
+    let mut integers = [const { Immediate(Value::Integer(0)) }; SMALL_INTEGER_COUNT];
+
+    let mut i = 0;
+    while i < SMALL_INTEGER_COUNT {
+        let integer = Immediate(Value::Integer(SMALL_INTEGER_MIN + i as i64));
+        core::mem::forget(core::mem::replace(&mut integers[i], integer));
+        i += 1;
+    }
+
+    integers
+};
+
+pub fn small_integer(n: i64) -> Option<&'static Immediate> {
+    if (SMALL_INTEGER_MIN..=SMALL_INTEGER_MAX).contains(&n) {
+        Some(&SMALL_INTEGERS[(n - SMALL_INTEGER_MIN) as usize])
+    } else {
+        None
+    }
+}
+
+const ASCII_COUNT: usize = 128;
+
+pub static ASCII_CHARS: [Immediate; ASCII_COUNT] = {
+    let mut chars = [const { Immediate(Value::Char('\0')) }; ASCII_COUNT];
+
+    let mut i = 0;
+    while i < ASCII_COUNT {
+        let c = Immediate(Value::Char(i as u8 as char));
+        core::mem::forget(core::mem::replace(&mut chars[i], c));
+        i += 1;
+    }
+
+    chars
+};
+
+pub fn ascii_char(c: char) -> Option<&'static Immediate> {
+    ASCII_CHARS.get(c as usize)
+}
@@ -1,159 +1,990 @@
-use crate::{pool::{RcValue, Pool}, value::Value, builtins::Builtins};
+use crate::{number::{Failure, Number, Op}, pool::{Heap, RcValue}, value::{equal, Value}};
+use crate::builtins::{Builtins, Fault, Library, NOT_A_NUMBER, NOT_AN_INTEGER, NOT_A_SYMBOL};
+use core::{fmt, mem};
 use core::ops::Deref;
 use heapless::{Vec, FnvIndexMap};
 
-#[inline(always)]
-pub fn eval_list<'s, Context, const N: usize, const BUILTINS: usize, const CELLS: usize>(
-    context: &mut Context,
-    pool: &'s Pool<'s, N>,
-    cells: &mut Cells<'s, CELLS>,
-    builtins: &Builtins<'s, Context, N, BUILTINS>,
-    list: RcValue<'s>
-) -> RcValue<'s> {
-    let mut stack: Vec<_, 16> = Vec::new();
+/// What a binding is found by: the name of a symbol, or the number of one
+/// made by `gensym`, which has no name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Name<'a> {
+    Text(&'a str),
+    Gensym(u64),
+}
 
-    let mut list = &list;
-    while let Value::Cons(car, cdr) = list.deref() {
-        stack.push(car).unwrap();
-        list = cdr;
+impl<'a> Name<'a> {
+    /// The name of `value`, if it is a symbol, for as long as `value` is
+    /// borrowed.
+    pub fn of(value: &'a Value<'_>) -> Option<Self> {
+        match value {
+            Value::Gensym(n) => Some(Name::Gensym(*n)),
+            value => value.name().map(Name::Text),
+        }
     }
 
-    let mut list = list.clone();
+    /// The name as if it lived for `'s`. The caller must not keep it past
+    /// whatever holds it: it is only looked up, or kept in an entry that
+    /// holds its symbol.
+    unsafe fn extend<'s>(self) -> Name<'s> {
+        match self {
+            Name::Text(name) => Name::Text(unsafe { &*(name as *const str) }),
+            Name::Gensym(n) => Name::Gensym(n),
+        }
+    }
+}
 
-    if let Value::Symbol("nil") = list.deref() {
-        stack.reverse();
-        for item in stack.into_iter() {
-            let car_ = eval(context, pool, cells, builtins, item.clone());
-            list = pool.new_cons(car_, list.clone());
+impl hash32::Hash for Name<'_> {
+    fn hash<H: hash32::Hasher>(&self, state: &mut H) {
+        match self {
+            Name::Text(name) => state.write(name.as_bytes()),
+            // Marked, so that a gensym hashes apart from a name of the same bytes.
+            Name::Gensym(n) => {
+                state.write(b"#");
+                state.write(&n.to_le_bytes());
+            },
         }
     }
+}
+
+/// What each name is bound to. An entry whose name is owned by its symbol,
+/// rather than borrowed for `'s`, holds the symbol so that the name lives
+/// as long as the entry.
+type Bindings<'s, const N: usize> = FnvIndexMap<Name<'s>, (Option<RcValue<'s>>, RcValue<'s>), N>;
+
+/// Binds `name` to `value`, returning what it was bound to before. An entry
+/// already there keeps its key, and whatever holds it.
+fn bind<'s, const N: usize>(
+    bindings: &mut Bindings<'s, N>,
+    name: Name<'s>,
+    symbol: Option<&RcValue<'s>>,
+    value: RcValue<'s>
+) -> Option<RcValue<'s>> {
+    if let Some((_, bound)) = bindings.get_mut(&name) {
+        return Some(mem::replace(bound, value));
+    }
 
-    return list;
+    let holder = symbol.filter(|symbol| symbol.is_named()).cloned();
+    bindings.insert(name, (holder, value)).unwrap();
+    None
 }
 
 pub struct Cells<'s, const N: usize> {
-    // functions: FnvIndexMap<&'s str, &'s Value<'s>, N>,
-    values: FnvIndexMap<&'s str, RcValue<'s>, N>
+    /// Each macro's parameter list consed onto its body.
+    macros: Bindings<'s, N>,
+    values: Bindings<'s, N>,
+    /// Expansions of macro calls, by the address of the calling form, which
+    /// is kept alive alongside so that the address cannot be reused.
+    expansions: Option<FnvIndexMap<usize, (RcValue<'s>, RcValue<'s>), N>>,
+    gensyms: usize,
+}
+
+impl<'s, const N: usize> Default for Cells<'s, N> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'s: 'cells, 'cells, const N: usize> Cells<'s, N> {
+impl<'s, const N: usize> Cells<'s, N> {
     pub fn new() -> Self {
         Cells {
-            // functions: FnvIndexMap::new(),
+            macros: FnvIndexMap::new(),
             values: FnvIndexMap::new(),
+            expansions: None,
+            gensyms: 0,
         }
     }
 
+    /// Remembers the expansion of each macro call, so that a call evaluated
+    /// again, as in the body of a loop, is not expanded again. Up to `N`
+    /// expansions are kept until a macro is redefined.
+    pub fn cache_expansions(&mut self) {
+        self.expansions = Some(FnvIndexMap::new());
+    }
+
     pub fn add_value(&mut self, key: &'s str, value: RcValue<'s>) {
-        self.values.insert(key, value).unwrap();
+        bind(&mut self.values, Name::Text(key), None, value);
     }
 }
 
-pub fn eval<'cells, 's: 'cells, Context, const N: usize, const BUILTINS: usize, const CELLS: usize>(
+/// Variable bindings as `eval` sees them, whatever their capacity.
+pub trait Scope<'s> {
+    fn get(&self, key: Name<'_>) -> Option<RcValue<'s>>;
+
+    /// Binds the symbol `key` to `value`, returning what it was bound to
+    /// before. Panics if `key` is not a symbol.
+    fn set(&mut self, key: &RcValue<'s>, value: RcValue<'s>) -> Option<RcValue<'s>>;
+
+    fn unset(&mut self, key: Name<'_>);
+
+    fn get_macro(&self, key: Name<'_>) -> Option<RcValue<'s>>;
+
+    fn set_macro(&mut self, key: &RcValue<'s>, expander: RcValue<'s>);
+
+    /// The cached expansion of `form`, if expansions are cached.
+    fn expansion(&self, form: &RcValue<'s>) -> Option<RcValue<'s>>;
+
+    fn cache_expansion(&mut self, form: RcValue<'s>, expansion: RcValue<'s>);
+
+    /// A number not returned before, for naming fresh symbols.
+    fn gensym(&mut self) -> usize;
+}
+
+impl<'s, const N: usize> Scope<'s> for Cells<'s, N> {
+    fn get(&self, key: Name<'_>) -> Option<RcValue<'s>> {
+        self.values.get(&unsafe { key.extend() }).map(|(_, value)| value.clone())
+    }
+
+    fn set(&mut self, key: &RcValue<'s>, value: RcValue<'s>) -> Option<RcValue<'s>> {
+        let Some(name) = Name::of(key) else { panic!() };
+        // A new entry holds `key` if the name is its own.
+        bind(&mut self.values, unsafe { name.extend() }, Some(key), value)
+    }
+
+    fn unset(&mut self, key: Name<'_>) {
+        self.values.remove(&unsafe { key.extend() });
+    }
+
+    fn get_macro(&self, key: Name<'_>) -> Option<RcValue<'s>> {
+        self.macros.get(&unsafe { key.extend() }).map(|(_, expander)| expander.clone())
+    }
+
+    fn set_macro(&mut self, key: &RcValue<'s>, expander: RcValue<'s>) {
+        let Some(name) = Name::of(key) else { panic!() };
+        bind(&mut self.macros, unsafe { name.extend() }, Some(key), expander);
+
+        if let Some(expansions) = &mut self.expansions {
+            expansions.clear();
+        }
+    }
+
+    fn expansion(&self, form: &RcValue<'s>) -> Option<RcValue<'s>> {
+        let (_, expansion) = self.expansions.as_ref()?.get(&form.addr())?;
+        Some(expansion.clone())
+    }
+
+    fn cache_expansion(&mut self, form: RcValue<'s>, expansion: RcValue<'s>) {
+        if let Some(expansions) = &mut self.expansions {
+            // A full cache only means the call is expanded again next time.
+            let _ = expansions.insert(form.addr(), (form, expansion));
+        }
+    }
+
+    fn gensym(&mut self) -> usize {
+        self.gensyms += 1;
+        self.gensyms
+    }
+}
+
+/// A non-local exit on its way out. While one is pending, `eval` returns
+/// nil from each form without evaluating any more of it, so that the forms
+/// around it undo their bindings and run their cleanup, until a form that
+/// handles it takes it.
+#[derive(Debug)]
+pub enum Unwind<'s> {
+    /// `(throw tag value)`, taken by a `catch` of an `equal` tag.
+    Throw(RcValue<'s>, RcValue<'s>),
+    /// `(error ...)`, with its condition, taken by `handler-case` or
+    /// `ignore-errors`.
+    Error(RcValue<'s>),
+    /// `(return-from name value)`, taken by the `block` of that name.
+    Return(RcValue<'s>, RcValue<'s>),
+    /// `(break)`, taken by the innermost `while`, which stops.
+    Break,
+    /// `(continue)`, taken by the innermost `while`, which goes on to test
+    /// its condition again.
+    Continue,
+}
+
+impl<'s> fmt::Display for Unwind<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unwind::Throw(tag, _) => write!(f, "no catch for {:?}", tag),
+            Unwind::Error(condition) => {
+                if let Value::Cons(kind, rest) = condition.deref() {
+                    match (kind.name(), rest.deref()) {
+                        (Some(kind), Value::Cons(message, _)) => if let Value::String(message) = message.deref() {
+                            return write!(f, "{}: {}", kind, message.as_str());
+                        },
+                        (Some(kind), Value::Symbol("nil")) => return write!(f, "{}", kind),
+                        _ => {},
+                    }
+                }
+
+                write!(f, "unhandled error {:?}", condition)
+            },
+            Unwind::Return(name, _) => match Name::of(name) {
+                Some(Name::Text(name)) => write!(f, "no block named {}", name),
+                Some(Name::Gensym(n)) => write!(f, "no block named #:g{}", n),
+                None => write!(f, "no block named {:?}", name),
+            },
+            Unwind::Break => write!(f, "break outside a loop"),
+            Unwind::Continue => write!(f, "continue outside a loop"),
+        }
+    }
+}
+
+const BAD_LOOP: Fault = Fault { kind: "program-error", message: "malformed loop" };
+const COLLECT_AND_SUM: Fault = Fault { kind: "program-error", message: "loop both collects and sums" };
+const TOO_MANY_VARIABLES: Fault = Fault { kind: "program-error", message: "too many do variables" };
+
+/// Whether `value` counts as true: anything but nil and zero.
+fn holds(value: &Value) -> bool {
+    !matches!(value, Value::Integer(0) | Value::Number(0.0) | Value::Symbol("nil"))
+}
+
+/// One clause of a `loop` after its `for`.
+enum Clause<'a, 's> {
+    Until(&'a RcValue<'s>),
+    Collect(&'a RcValue<'s>),
+    Sum(&'a RcValue<'s>),
+    Do(&'a RcValue<'s>),
+}
+
+/// What a `loop` goes through: nothing in particular, the rest of a list,
+/// or integers from the next up to the last, if there is a last.
+enum Driver<'s> {
+    Forever,
+    In(RcValue<'s>),
+    Range(i128, Option<i128>),
+}
+
+/// Everything evaluation needs, handed to builtins so they can evaluate
+/// forms of their own.
+pub struct Env<'e, 's, Context> {
+    pub context: &'e mut Context,
+    pub pool: &'s dyn Heap<'s>,
+    pub cells: &'e mut dyn Scope<'s>,
+    pub builtins: &'e dyn Library<'s, Context>,
+    /// The exit in progress, if any; builtins set it to signal one.
+    pub unwind: Option<Unwind<'s>>,
+}
+
+/// Evaluates `ast`, returning its value, or the exit that nothing in it
+/// took, such as an error with no handler.
+pub fn eval<'s, Context, const BUILTINS: usize, const CELLS: usize>(
     context: &mut Context,
-    pool: &'s Pool<'s, N>,
-    cells: &'cells mut Cells<'s, CELLS>,
-    builtins: &Builtins<'s, Context, N, BUILTINS>,
+    pool: &'s dyn Heap<'s>,
+    cells: &mut Cells<'s, CELLS>,
+    builtins: &Builtins<'s, Context, BUILTINS>,
     ast: RcValue<'s>
-) -> RcValue<'s> {
-    match ast.deref() {
-        Value::Cons(car, ast) => {
-            match car.deref() {
-                Value::Symbol("progn") => {
-                    let mut ast = ast;
-
-                    let mut result = pool.new_symbol("nil");
-
-                    while let Value::Cons(car, cdr) = ast.deref() {
-                        result = eval(context, pool, cells, builtins, car.clone());
-                        ast = &cdr;
+) -> Result<RcValue<'s>, Unwind<'s>> {
+    let mut env = Env { context, pool, cells, builtins, unwind: None };
+    let result = env.eval(ast);
+    match env.unwind {
+        Some(unwind) => Err(unwind),
+        None => Ok(result),
+    }
+}
+
+impl<'e, 's, Context> Env<'e, 's, Context> {
+    /// Whether an exit is in progress, so that evaluation should stop.
+    pub fn unwinding(&self) -> bool {
+        self.unwind.is_some()
+    }
+
+    /// Signals `fault` as an error whose condition is `(kind "message")`,
+    /// as `(error 'kind "message")` would, and returns nil in place of the
+    /// result a builtin could not give. A pool with no arena room for the
+    /// message gets just `(kind)`.
+    pub fn fail(&mut self, fault: Fault) -> RcValue<'s> {
+        let pool = self.pool;
+        let nil = pool.new_symbol("nil");
+        let message = match pool.new_string(fault.message) {
+            Some(message) => pool.new_cons(message, nil.clone()),
+            None => nil.clone(),
+        };
+        self.unwind = Some(Unwind::Error(pool.new_cons(pool.new_symbol(fault.kind), message)));
+
+        nil
+    }
+
+    /// The value in `result`, or nil after signalling its fault.
+    pub fn or_fail(&mut self, result: Result<RcValue<'s>, Fault>) -> RcValue<'s> {
+        result.unwrap_or_else(|fault| self.fail(fault))
+    }
+
+    /// Evaluates each of `forms` in turn, returning the last result, or nil
+    /// if there are none or an exit cuts them short.
+    pub fn eval_body(&mut self, forms: &RcValue<'s>) -> RcValue<'s> {
+        let mut result = self.pool.new_symbol("nil");
+
+        let mut forms = forms;
+        while let Value::Cons(car, cdr) = forms.deref() {
+            if self.unwinding() {
+                return self.pool.new_symbol("nil");
+            }
+            result = self.eval(car.clone());
+            forms = cdr;
+        }
+
+        result
+    }
+
+    /// Binds `key` back to `old_value`, or unbinds it if it had none.
+    fn restore(&mut self, key: &RcValue<'s>, old_value: Option<RcValue<'s>>) {
+        match old_value {
+            Some(old_value) => { self.cells.set(key, old_value); },
+            None => self.cells.unset(Name::of(key).unwrap()),
+        }
+    }
+
+    /// Evaluates each element of `list`, returning a list of the results.
+    pub fn eval_list(&mut self, list: RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let mut stack: Vec<_, 16> = Vec::new();
+
+        let mut list = &list;
+        while let Value::Cons(car, cdr) = list.deref() {
+            stack.push(car).unwrap();
+            list = cdr;
+        }
+
+        let mut list = list.clone();
+
+        if let Value::Symbol("nil") = list.deref() {
+            stack.reverse();
+            for item in stack.into_iter() {
+                if self.unwinding() {
+                    break;
+                }
+                let car_ = self.eval(item.clone());
+                list = pool.new_cons(car_, list.clone());
+            }
+        }
+
+        list
+    }
+
+    /// Expands `form` once if it is a call to a macro.
+    pub fn macroexpand_1(&mut self, form: &RcValue<'s>) -> Option<RcValue<'s>> {
+        let Value::Cons(name, args) = form.deref() else { return None };
+        let name = Name::of(name)?;
+        let expander = self.cells.get_macro(name)?;
+
+        if let Some(expansion) = self.cells.expansion(form) {
+            return Some(expansion);
+        }
+
+        let expansion = self.expand(&expander, args);
+        if self.unwinding() {
+            return Some(expansion);
+        }
+        self.cells.cache_expansion(form.clone(), expansion.clone());
+
+        Some(expansion)
+    }
+
+    /// Runs a macro's body with its parameters bound to the unevaluated
+    /// arguments. A parameter after `&rest` takes the remaining arguments.
+    fn expand(&mut self, expander: &RcValue<'s>, args: &RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let Value::Cons(params, body) = expander.deref() else { panic!() };
+
+        let mut bound: Vec<(&RcValue<'s>, Option<RcValue<'s>>), 16> = Vec::new();
+        let mut params = params.deref();
+        let mut args = args.clone();
+        while let Value::Cons(param, rest) = params {
+            if param.name() == Some("&rest") {
+                let Value::Cons(param, _) = rest.deref() else { panic!() };
+                bound.push((param, self.cells.set(param, args))).unwrap();
+                break;
+            }
+
+            let (value, rest_args) = match args.deref() {
+                Value::Cons(arg, rest) => (arg.clone(), rest.clone()),
+                _ => (pool.new_symbol("nil"), args.clone())
+            };
+            bound.push((param, self.cells.set(param, value))).unwrap();
+            args = rest_args;
+            params = rest;
+        }
+
+        let result = self.eval_body(body);
+
+        while let Some((param, old_value)) = bound.pop() {
+            self.restore(param, old_value);
+        }
+
+        result
+    }
+
+    /// Takes a `break` or `continue` meant for the loop whose body just ran,
+    /// returning whether the loop should stop: after a `break`, or while any
+    /// other exit passes through.
+    fn loop_exit(&mut self) -> bool {
+        match self.unwind {
+            Some(Unwind::Break) => {
+                self.unwind = None;
+                true
+            },
+            Some(Unwind::Continue) => {
+                self.unwind = None;
+                false
+            },
+            _ => self.unwinding(),
+        }
+    }
+
+    /// `(dotimes (var count result) body...)` runs `body` with `var` bound
+    /// to each integer from zero up to `count`, then returns `result`,
+    /// evaluated with `var` bound to `count`, or nil.
+    fn dotimes(&mut self, ast: &RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let Value::Cons(spec, body) = ast.deref() else { panic!() };
+        let Value::Cons(var, spec) = spec.deref() else { panic!() };
+        let Value::Cons(count, result) = spec.deref() else { panic!() };
+
+        let count = self.eval(count.clone());
+        if self.unwinding() {
+            return pool.new_symbol("nil");
+        }
+        let Value::Integer(count) = *count else { return self.fail(NOT_AN_INTEGER) };
+        let count = count.max(0);
+
+        let old_value = self.cells.set(var, pool.new_integer(0));
+        let mut stopped = false;
+        for n in 0..count {
+            self.cells.set(var, pool.new_integer(n));
+            self.eval_body(body);
+            if self.loop_exit() {
+                stopped = true;
+                break;
+            }
+        }
+
+        let result = match stopped {
+            true => pool.new_symbol("nil"),
+            false => {
+                self.cells.set(var, pool.new_integer(count));
+                self.eval_body(result)
+            },
+        };
+        self.restore(var, old_value);
+
+        result
+    }
+
+    /// `(dolist (var list result) body...)` runs `body` with `var` bound to
+    /// each element of `list`, then returns `result`, evaluated with `var`
+    /// bound to nil, or nil.
+    fn dolist(&mut self, ast: &RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let Value::Cons(spec, body) = ast.deref() else { panic!() };
+        let Value::Cons(var, spec) = spec.deref() else { panic!() };
+        let Value::Cons(list, result) = spec.deref() else { panic!() };
+
+        let mut list = self.eval(list.clone());
+        if self.unwinding() {
+            return pool.new_symbol("nil");
+        }
+
+        let old_value = self.cells.set(var, pool.new_symbol("nil"));
+        let mut stopped = false;
+        while let Value::Cons(element, rest) = list.deref() {
+            let rest = rest.clone();
+            self.cells.set(var, element.clone());
+            self.eval_body(body);
+            if self.loop_exit() {
+                stopped = true;
+                break;
+            }
+            list = rest;
+        }
+
+        let result = match stopped {
+            true => pool.new_symbol("nil"),
+            false => {
+                self.cells.set(var, pool.new_symbol("nil"));
+                self.eval_body(result)
+            },
+        };
+        self.restore(var, old_value);
+
+        result
+    }
+
+    /// `(do ((var init step)...) (test result...) body...)` binds each `var`
+    /// to `init`, then until `test` holds, runs `body` and moves each `var`
+    /// with a `step` on to it. The inits, and each round of steps, are all
+    /// evaluated before any are bound. Returns the last `result`, or nil.
+    fn do_(&mut self, ast: &RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let Value::Cons(specs, rest) = ast.deref() else { panic!() };
+        let Value::Cons(end, body) = rest.deref() else { panic!() };
+        let Value::Cons(test, results) = end.deref() else { panic!() };
+
+        let mut values: Vec<(&RcValue<'s>, RcValue<'s>), 16> = Vec::new();
+        let mut specs_left = specs.deref();
+        while let Value::Cons(spec, rest) = specs_left {
+            let Value::Cons(var, init) = spec.deref() else { panic!() };
+            let value = match init.deref() {
+                Value::Cons(init, _) => self.eval(init.clone()),
+                _ => pool.new_symbol("nil"),
+            };
+            if self.unwinding() {
+                return pool.new_symbol("nil");
+            }
+            if values.push((var, value)).is_err() {
+                return self.fail(TOO_MANY_VARIABLES);
+            }
+            specs_left = rest;
+        }
+
+        let mut bound: Vec<(&RcValue<'s>, Option<RcValue<'s>>), 16> = Vec::new();
+        while let Some((var, value)) = values.pop() {
+            bound.push((var, self.cells.set(var, value))).unwrap();
+        }
+
+        let mut result = pool.new_symbol("nil");
+        loop {
+            let done = self.eval(test.clone());
+            if self.unwinding() {
+                break;
+            }
+            if holds(&done) {
+                result = self.eval_body(results);
+                break;
+            }
+
+            self.eval_body(body);
+            if self.loop_exit() {
+                break;
+            }
+
+            let mut specs_left = specs.deref();
+            while let Value::Cons(spec, rest) = specs_left {
+                let Value::Cons(var, init) = spec.deref() else { panic!() };
+                if let Value::Cons(_, step) = init.deref() {
+                    if let Value::Cons(step, _) = step.deref() {
+                        values.push((var, self.eval(step.clone()))).unwrap();
                     }
+                }
+                specs_left = rest;
+            }
+            if self.unwinding() {
+                break;
+            }
+            while let Some((var, value)) = values.pop() {
+                self.cells.set(var, value);
+            }
+        }
+
+        while let Some((var, old_value)) = bound.pop() {
+            self.restore(var, old_value);
+        }
+
+        result
+    }
+
+    /// `(loop clauses...)`: an optional `for var in list`, or `for var from
+    /// a` with `to b` or `below b`, then any of `until test`, `collect form`,
+    /// `sum form` and `do form`, run in order each time round. The loop ends
+    /// when the `for` runs out or an `until` holds, and returns the list of
+    /// what was collected, or the sum, or nil.
+    fn loop_(&mut self, ast: &RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+        let mut clauses_left = ast.deref();
 
-                    result
+        let mut var = None;
+        let mut driver = Driver::Forever;
+        if let Value::Cons(word, rest) = clauses_left {
+            if word.name() == Some("for") {
+                let Value::Cons(name, rest) = rest.deref() else { return self.fail(BAD_LOOP) };
+                let Value::Cons(how, rest) = rest.deref() else { return self.fail(BAD_LOOP) };
+                let Value::Cons(start, rest) = rest.deref() else { return self.fail(BAD_LOOP) };
+                if Name::of(name).is_none() {
+                    return self.fail(NOT_A_SYMBOL);
                 }
-                Value::Symbol("let-") => {
-                    if let Value::Cons(binding, ast) = ast.deref() {
-                        if let Value::Cons(key, value) = binding.deref() {
-                            if let Value::Symbol(key) = key.deref() {
-                                let value = eval(context, pool, cells, builtins, value.clone());
+                let start = self.eval(start.clone());
+                if self.unwinding() {
+                    return pool.new_symbol("nil");
+                }
+                clauses_left = rest;
+
+                driver = match how.name() {
+                    Some("in") => Driver::In(start),
+                    Some("from") => {
+                        let Value::Integer(start) = *start else { return self.fail(NOT_AN_INTEGER) };
+
+                        let mut last = None;
+                        if let Value::Cons(word, rest) = clauses_left {
+                            if let Some(word @ ("to" | "below")) = word.name() {
+                                let Value::Cons(end, rest) = rest.deref() else { return self.fail(BAD_LOOP) };
+                                let end = self.eval(end.clone());
+                                if self.unwinding() {
+                                    return pool.new_symbol("nil");
+                                }
+                                let Value::Integer(end) = *end else { return self.fail(NOT_AN_INTEGER) };
+
+                                last = Some(if word == "to" { end as i128 } else { end as i128 - 1 });
+                                clauses_left = rest;
+                            }
+                        }
+
+                        Driver::Range(start as i128, last)
+                    },
+                    _ => return self.fail(BAD_LOOP),
+                };
+                var = Some(name);
+            }
+        }
+
+        let mut clauses: Vec<Clause<'_, 's>, 16> = Vec::new();
+        while let Value::Cons(word, rest) = clauses_left {
+            let Value::Cons(form, rest) = rest.deref() else { return self.fail(BAD_LOOP) };
+            let clause = match word.name() {
+                Some("until") => Clause::Until(form),
+                Some("collect") => Clause::Collect(form),
+                Some("sum") => Clause::Sum(form),
+                Some("do") => Clause::Do(form),
+                _ => return self.fail(BAD_LOOP),
+            };
+            if clauses.push(clause).is_err() {
+                return self.fail(BAD_LOOP);
+            }
+            clauses_left = rest;
+        }
+
+        let collects = clauses.iter().any(|clause| matches!(clause, Clause::Collect(_)));
+        let sums = clauses.iter().any(|clause| matches!(clause, Clause::Sum(_)));
+        if collects && sums {
+            return self.fail(COLLECT_AND_SUM);
+        }
 
-                                let old_value = cells.values.get(key).map(|x| x.clone());
-                                cells.values.insert(key, value).unwrap();
+        let old_value = var.and_then(|var| self.cells.get(Name::of(var)?));
+        let mut collected = pool.new_symbol("nil");
+        let mut sum = Number::Integer(0);
+        let mut stopped = false;
+        'rounds: loop {
+            let value = match &mut driver {
+                Driver::Forever => None,
+                Driver::In(list) => {
+                    let Value::Cons(element, rest) = &**list else { break };
+                    let element = element.clone();
+                    let rest = rest.clone();
+                    *list = rest;
+                    Some(element)
+                },
+                Driver::Range(next, last) => {
+                    if last.is_some_and(|last| *next > last) {
+                        break;
+                    }
+                    // Past the largest integer, there is no next one to take.
+                    let Ok(n) = i64::try_from(*next) else {
+                        self.fail(Failure::Overflow.into());
+                        stopped = true;
+                        break;
+                    };
+                    *next += 1;
+                    Some(pool.new_integer(n))
+                },
+            };
+            if let (Some(var), Some(value)) = (var, value) {
+                self.cells.set(var, value);
+            }
 
-                                let mut result = pool.new_symbol("nil");
+            for clause in clauses.iter() {
+                match clause {
+                    Clause::Until(test) => {
+                        let done = self.eval((*test).clone());
+                        if !self.unwinding() && holds(&done) {
+                            break 'rounds;
+                        }
+                    },
+                    Clause::Collect(form) => {
+                        let value = self.eval((*form).clone());
+                        if !self.unwinding() {
+                            collected = pool.new_cons(value, collected);
+                        }
+                    },
+                    Clause::Sum(form) => {
+                        let value = self.eval((*form).clone());
+                        if !self.unwinding() {
+                            let added = Number::from_value(&value).ok_or(NOT_A_NUMBER)
+                                .and_then(|number| Ok(sum.clone().combine(number, Op::Add)?));
+                            match added {
+                                Ok(added) => sum = added,
+                                Err(fault) => { self.fail(fault); },
+                            }
+                        }
+                    },
+                    Clause::Do(form) => {
+                        self.eval((*form).clone());
+                    },
+                }
+                if self.unwinding() {
+                    break;
+                }
+            }
+
+            if self.loop_exit() {
+                stopped = true;
+                break;
+            }
+        }
 
-                                let mut ast = ast;
-                                while let Value::Cons(car, cdr) = ast.deref() {
-                                    result = eval(context, pool, cells, builtins, car.clone());
-                                    ast = cdr;
+        if let Some(var) = var {
+            self.restore(var, old_value);
+        }
+
+        if stopped {
+            return pool.new_symbol("nil");
+        }
+        if sums {
+            return sum.into_value(pool);
+        }
+
+        // Collected values were consed on in reverse. No one else holds the
+        // list yet, so it is turned around in place.
+        let mut list = pool.new_symbol("nil");
+        while let Value::Cons(..) = collected.deref() {
+            let mut cell = mem::replace(&mut collected, pool.new_symbol("nil"));
+            let Some(Value::Cons(_, rest)) = cell.get_mut() else { unreachable!() };
+            collected = mem::replace(rest, list);
+            list = cell;
+        }
+
+        list
+    }
+
+    pub fn eval(&mut self, ast: RcValue<'s>) -> RcValue<'s> {
+        let pool = self.pool;
+
+        if let Some(expansion) = self.macroexpand_1(&ast) {
+            if self.unwinding() {
+                return pool.new_symbol("nil");
+            }
+            return self.eval(expansion);
+        }
+
+        match ast.deref() {
+            Value::Cons(car, ast) => {
+                match car.name() {
+                    Some("quote") => {
+                        if let Value::Cons(car, _) = ast.deref() {
+                            return car.clone();
+                        }
+
+                        panic!()
+                    },
+                    Some("defmacro") => {
+                        if let Value::Cons(name, expander) = ast.deref() {
+                            if Name::of(name).is_some() {
+                                self.cells.set_macro(name, expander.clone());
+
+                                return name.clone();
+                            }
+                        }
+
+                        panic!()
+                    },
+                    Some("progn") => self.eval_body(ast),
+                    Some("let-") => {
+                        if let Value::Cons(binding, ast) = ast.deref() {
+                            if let Value::Cons(key, value) = binding.deref() {
+                                if Name::of(key).is_some() {
+                                    let value = self.eval(value.clone());
+
+                                    if self.unwinding() {
+                                        return pool.new_symbol("nil");
+                                    }
+
+                                    let old_value = self.cells.set(key, value);
+                                    let result = self.eval_body(ast);
+                                    self.restore(key, old_value);
+
+                                    return result;
                                 }
+                            }
+                        }
+
+                        panic!()
+                    },
+                    Some("set") => {
+                        if let Value::Cons(key, ast) = ast.deref() {
+                            if let Value::Cons(value, _) = ast.deref() {
+                                if Name::of(key).is_some() {
+                                    let value = self.eval(value.clone());
+                                    if !self.unwinding() {
+                                        self.cells.set(key, value);
+                                    }
 
-                                if let Some(old_value) = old_value {
-                                    cells.values.insert(key, old_value).unwrap();
-                                } else {
-                                    cells.values.remove(key).unwrap();
+                                    return pool.new_symbol("nil")
+                                }
+                            }
+                        }
+
+                        panic!()
+                    },
+                    Some("while") => {
+                        if let Value::Cons(condition, ast) = ast.deref() {
+                            // A `break` or `continue` in the condition acts
+                            // on this loop just as one in the body does.
+                            loop {
+                                let test = self.eval(condition.clone());
+                                if !self.unwinding() {
+                                    if !holds(&test) {
+                                        break;
+                                    }
+                                    self.eval_body(ast);
+                                }
+                                if self.loop_exit() {
+                                    break;
                                 }
+                            }
+
+                            return pool.new_symbol("nil");
+                        }
 
-                                return result;
+                        panic!()
+                    },
+                    Some("dotimes") => self.dotimes(ast),
+                    Some("dolist") => self.dolist(ast),
+                    Some("do") => self.do_(ast),
+                    Some("loop") => self.loop_(ast),
+                    Some("block") => {
+                        if let Value::Cons(name, body) = ast.deref() {
+                            if let Some(name) = Name::of(name) {
+                                let result = self.eval_body(body);
+                                return match self.unwind.take() {
+                                    Some(Unwind::Return(returned, value)) if Name::of(&returned) == Some(name) => value,
+                                    unwind => {
+                                        self.unwind = unwind;
+                                        result
+                                    },
+                                };
                             }
                         }
-                    }
 
-                    panic!()
-                },
-                Value::Symbol("set") => {
-                    if let Value::Cons(key, ast) = ast.deref() {
-                        if let Value::Cons(value, _) = ast.deref() {
-                            if let Value::Symbol(key) = key.deref() {
-                                let value = eval(context, pool, cells, builtins, value.clone());
-                                cells.add_value(key, value);
-
-                                return pool.new_symbol("nil")
+                        panic!()
+                    },
+                    Some("return-from") => {
+                        if let Value::Cons(name, rest) = ast.deref() {
+                            if Name::of(name).is_some() {
+                                let value = match rest.deref() {
+                                    Value::Cons(value, _) => self.eval(value.clone()),
+                                    _ => pool.new_symbol("nil"),
+                                };
+                                if !self.unwinding() {
+                                    self.unwind = Some(Unwind::Return(name.clone(), value));
+                                }
+
+                                return pool.new_symbol("nil");
                             }
                         }
-                    }
 
-                    panic!()
-                },
-                Value::Symbol("while") => {
-                    if let Value::Cons(condition, ast) = ast.deref() {
-                        while {
-                            match *eval(context, pool, cells, builtins, condition.clone()) {
-                                Value::Integer(0) => false,
-                                Value::Number(n) if n == 0.0 => false,
-                                Value::Symbol("nil") => false,
-                                _ => true
+                        panic!()
+                    },
+                    Some("catch") => {
+                        if let Value::Cons(tag, body) = ast.deref() {
+                            let tag = self.eval(tag.clone());
+                            if self.unwinding() {
+                                return pool.new_symbol("nil");
                             }
-                        }{
-                            let mut ast = ast;
 
-                            while let Value::Cons(car, cdr) = ast.deref() {
-                                eval(context, pool, cells, builtins, car.clone());
-                                ast = cdr;
+                            let result = self.eval_body(body);
+                            return match self.unwind.take() {
+                                Some(Unwind::Throw(thrown, value)) if equal(&thrown, &tag) => value,
+                                unwind => {
+                                    self.unwind = unwind;
+                                    result
+                                },
+                            };
+                        }
+
+                        panic!()
+                    },
+                    Some("handler-case") => {
+                        if let Value::Cons(form, clauses) = ast.deref() {
+                            let result = self.eval(form.clone());
+                            let Some(Unwind::Error(condition)) = &self.unwind else { return result };
+                            let Value::Cons(kind, _) = condition.deref() else { panic!() };
+                            let condition = condition.clone();
+
+                            // A clause for `error` handles every condition,
+                            // others only those of their own type.
+                            let mut clauses = clauses.deref();
+                            while let Value::Cons(clause, rest) = clauses {
+                                let Value::Cons(handles, clause) = clause.deref() else { panic!() };
+                                let Value::Cons(var, body) = clause.deref() else { panic!() };
+                                if handles.name() != Some("error") && !equal(handles, kind) {
+                                    clauses = rest;
+                                    continue;
+                                }
+
+                                self.unwind = None;
+                                return match var.deref() {
+                                    Value::Cons(var, _) => {
+                                        let old_value = self.cells.set(var, condition);
+                                        let result = self.eval_body(body);
+                                        self.restore(var, old_value);
+
+                                        result
+                                    },
+                                    _ => self.eval_body(body),
+                                };
                             }
+
+                            return result;
                         }
 
-                        return pool.new_symbol("nil");
-                    }
+                        panic!()
+                    },
+                    Some("ignore-errors") => {
+                        let result = self.eval_body(ast);
+                        if let Some(Unwind::Error(_)) = self.unwind {
+                            self.unwind = None;
+                            return pool.new_symbol("nil");
+                        }
 
-                    panic!()
-                },
-                Value::Symbol(builtin) => {
-                    if let Some(f) = builtins.get(builtin) {
-                        let list = eval_list(context, pool, cells, builtins, ast.clone());
+                        result
+                    },
+                    Some("unwind-protect") => {
+                        if let Value::Cons(form, cleanup) = ast.deref() {
+                            let result = self.eval(form.clone());
 
-                        return f(context, pool, list);
-                    }
+                            // Cleanup runs whether or not the form exited,
+                            // and an exit of its own replaces that one.
+                            let unwind = self.unwind.take();
+                            self.eval_body(cleanup);
+                            if !self.unwinding() {
+                                self.unwind = unwind;
+                            }
 
-                    return pool.new_symbol("nil");
-                },
-                _ => panic!()
+                            return result;
+                        }
+
+                        panic!()
+                    },
+                    Some(builtin) => {
+                        if let Some(f) = self.builtins.builtin(builtin) {
+                            let list = self.eval_list(ast.clone());
+                            if self.unwinding() {
+                                return pool.new_symbol("nil");
+                            }
+
+                            return f(self, list);
+                        }
+
+                        pool.new_symbol("nil")
+                    },
+                    None => panic!()
+                }
             }
+            Value::Symbol("nil") => ast,
+            Value::Integer(_) => ast,
+            Value::Number(_) => ast,
+            Value::Ratio(_, _) => ast,
+            Value::Char(_) => ast,
+            Value::Vector(_) => ast,
+            Value::Table(_) => ast,
+            Value::Bytes(_) => ast,
+            Value::String(_) => ast,
+            #[cfg(feature = "bigint")]
+            Value::BigInt(_) => ast,
+            Value::Symbol(_) | Value::Named(_) | Value::Gensym(_) => {
+                self.cells.get(Name::of(&ast).unwrap()).unwrap_or_else(|| pool.new_symbol("nil"))
+            },
         }
-        Value::Symbol("nil") => ast,
-        Value::Integer(_) => ast,
-        Value::Number(_) => ast,
-        Value::Symbol(symbol) => cells.values.get(symbol).map(|x| x.clone()).unwrap_or_else(|| pool.new_symbol("nil")),
-        _ => panic!()
     }
 }
@@ -1,7 +1,15 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod arena;
 pub mod builtins;
 pub mod constants;
 pub mod eval;
+pub mod number;
 pub mod parser;
 pub mod pool;
+pub mod table;
 pub mod tokenizer;
 pub mod value;
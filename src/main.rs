@@ -1,11 +1,9 @@
-#![feature(as_array_of_cells)]
-
 use myser::{
     builtins::{Builtin, Builtins},
-    eval::{eval, Cells},
-    parser::parse,
-    pool::{RcValue, Pool},
-    value::Value,
+    eval::{eval, Cells, Env, Unwind},
+    parser::{self, parse},
+    pool::{GrowablePool, Heap, RcValue, StaticPool},
+    value::{BytesLiteral, CharLiteral, Value},
 };
 use core::ops::Deref;
 use std::io::Write;
@@ -41,15 +39,37 @@ impl Context {
     }
 }
 
-fn print<'s, Context: HasStdout, const N: usize>(context: &mut Context, pool: &'s Pool<'s, N>, args: RcValue<'s>) -> RcValue<'s> {
+fn print<'s, Context: HasStdout>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+    let context = &mut *env.context;
+
     if let Value::Cons(car, cdr) = args.deref() {
         if let Value::Symbol("nil") = cdr.deref() {
             match car.deref() {
                 Value::Integer(n) => {
-                    write!(context.stdout(), "{}\n", n).unwrap();
+                    writeln!(context.stdout(), "{}", n).unwrap();
+                },
+                Value::String(text) => {
+                    writeln!(context.stdout(), "{}", text.as_str()).unwrap();
+                },
+                Value::Ratio(n, d) => {
+                    writeln!(context.stdout(), "{}/{}", n, d).unwrap();
+                },
+                Value::Gensym(n) => {
+                    writeln!(context.stdout(), "#:g{}", n).unwrap();
+                },
+                Value::Char(c) => {
+                    writeln!(context.stdout(), "{}", CharLiteral(*c)).unwrap();
+                },
+                Value::Bytes(buffer) => {
+                    writeln!(context.stdout(), "{}", BytesLiteral(buffer)).unwrap();
+                },
+                #[cfg(feature = "bigint")]
+                Value::BigInt(n) => {
+                    writeln!(context.stdout(), "{}", n).unwrap();
                 },
                 car => {
-                    write!(context.stdout(), "{:?}\n", car).unwrap();
+                    writeln!(context.stdout(), "{:?}", car).unwrap();
                 }
             }
 
@@ -57,42 +77,152 @@ fn print<'s, Context: HasStdout, const N: usize>(context: &mut Context, pool: &'
         }
     }
 
-    write!(context.stdout(), "{:?}\n", args).unwrap();
+    writeln!(context.stdout(), "{:?}", args).unwrap();
     pool.new_symbol("nil")
 }
 
-fn read<'s, Context: HasStdin, const N: usize>(context: &mut Context, pool: &'s Pool<'s, N>, _: RcValue<'s>) -> RcValue<'s> {
+/// Whether `source` holds a whole datum: something other than whitespace,
+/// with every list and string it opens closed again. The parenthesis in a
+/// character like `#\(` opens nothing.
+fn complete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut string = false;
+    let mut escaped = false;
+
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match (string, escaped, c) {
+            (true, true, _) => escaped = false,
+            (true, false, '\\') => escaped = true,
+            (true, false, '"') => string = false,
+            (true, false, _) => {},
+            (false, _, '"') => string = true,
+            (false, _, '#') if chars.as_str().starts_with('\\') => { chars.nth(1); },
+            (false, _, '(') => depth += 1,
+            (false, _, ')') => depth -= 1,
+            _ => {},
+        }
+    }
+
+    depth <= 0 && !string && !source.trim().is_empty()
+}
+
+/// Reads one datum from the input, taking more lines while it is incomplete.
+fn read<'s, Context: HasStdin>(env: &mut Env<'_, 's, Context>, _: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
     let mut buffer = String::new();
-    context.stdin().read_line(&mut buffer).unwrap();
 
-    if let Ok(n) = buffer.trim().parse() {
-        return pool.new_integer(n);
+    while env.context.stdin().read_line(&mut buffer).unwrap() > 0 {
+        if complete(&buffer) {
+            return match parser::read(pool, &buffer) {
+                Ok((_, form)) => form,
+                Err(_) => signal(env, "parse-error", "unreadable input"),
+            };
+        }
     }
 
     pool.new_symbol("nil")
 }
 
-fn main() {
-    let mut buffer = String::new();
+/// Signals an error of type `kind`, as `(error 'kind "message")` would, or
+/// as `(error 'kind)` if there is no room for the message.
+fn signal<'s, Context>(env: &mut Env<'_, 's, Context>, kind: &'static str, message: &str) -> RcValue<'s> {
+    let pool = env.pool;
+    let message = match pool.new_string(message) {
+        Some(message) => pool.new_cons(message, pool.new_symbol("nil")),
+        None => pool.new_symbol("nil"),
+    };
+    env.unwind = Some(Unwind::Error(pool.new_cons(pool.new_symbol(kind), message)));
+
+    pool.new_symbol("nil")
+}
+
+/// Evaluates every form in a file, returning the value of the last. A file
+/// that cannot be read, or a form in it that cannot be parsed, signals an
+/// error.
+fn load<'s, Context>(env: &mut Env<'_, 's, Context>, args: RcValue<'s>) -> RcValue<'s> {
+    let pool = env.pool;
+
+    if let Value::Cons(car, _) = args.deref() {
+        if let Value::String(path) = car.deref() {
+            let source = match std::fs::read_to_string(path.as_str()) {
+                Ok(source) => source,
+                Err(error) => return signal(env, "file-error", &error.to_string()),
+            };
+            let mut source = source.as_str();
+
+            let mut result = pool.new_symbol("nil");
+            while !source.trim().is_empty() && !env.unwinding() {
+                let Ok((rest, form)) = parser::read(pool, source) else {
+                    return signal(env, "parse-error", path.as_str());
+                };
+                result = env.eval(form);
+                source = rest;
+            }
+
+            return result;
+        }
+    }
+
+    pool.new_symbol("nil")
+}
 
-    let pool: Pool<'_, 10000> = Pool::new();
-    let mut builtins: Builtins<'_, _, 10000, 64> = Builtins::new();
-    builtins.add("print", print as Builtin<'_, _, 10000>);
-    builtins.add("read", read as Builtin<'_, _, 10000>);
+fn run<'s>(pool: &'s dyn Heap<'s>, source: &'s str, stats: bool) {
+    let mut builtins: Builtins<'_, _, 128> = Builtins::new().with_math().with_bits().with_types().with_vectors().with_tables().with_bytes();
+    builtins.add("print", print as Builtin<'_, _>);
+    builtins.add("read", read as Builtin<'_, _>);
+    builtins.add("load", load as Builtin<'_, _>);
 
     let mut context = Context::new(std::io::stdin(), std::io::stdout());
     let mut cells: Cells<'_, 16> = Cells::new();
+    cells.cache_expansions();
 
-    std::io::stdin().read_line(&mut buffer).unwrap();
-
-    let result = parse(&pool, &buffer).unwrap().1;
+    let result = parse(pool, source).unwrap().1;
     // println!("{:?}", result);
-    // println!("{:?}", pool.used());
-    eval(&mut context, &pool, &mut cells, &builtins, result);
+    let outcome = eval(&mut context, pool, &mut cells, &builtins, result);
+
+    if stats {
+        eprint!("{}", pool.stats());
+    }
+    if let Err(unwind) = outcome {
+        eprintln!("{}", unwind);
+        std::process::exit(1);
+    }
     // println!("{:?}", result);
     // println!("{:?}", eval(&pool, result, &builtins));
 
-    // println!("{}", pool.used());
     // println!("{:?}", pool.new_cons(&ZERO, &ONE));
     // println!("{:?}", eval(&pool.new_cons(&PLUS, pool.new_cons(&TWO, pool.new_cons(&PLUS, pool.new_cons(&TWO, &ONE))))));
 }
+
+static POOL: StaticPool<10000, 65536> = StaticPool::new();
+
+fn main() {
+    let mut buffer = String::new();
+
+    let mut stats = false;
+    // `None` keeps the fixed 10000-cell pool; `Some(None)` grows without bound.
+    let mut pool_size: Option<Option<usize>> = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            "--pool-size" => pool_size = match args.next().as_deref() {
+                Some("unbounded") => Some(None),
+                Some(cells) => Some(Some(cells.parse().expect("--pool-size takes a number of cells or `unbounded`"))),
+                None => panic!("--pool-size takes a number of cells or `unbounded`"),
+            },
+            arg => panic!("unknown argument {}", arg),
+        }
+    }
+
+    std::io::stdin().read_line(&mut buffer).unwrap();
+    let buffer: &'static str = buffer.leak();
+
+    match pool_size {
+        None => run(POOL.take().unwrap(), buffer, stats),
+        Some(None) => run(&GrowablePool::new(), buffer, stats),
+        Some(Some(cells)) => run(&GrowablePool::with_limit(cells), buffer, stats),
+    }
+}
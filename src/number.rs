@@ -0,0 +1,539 @@
+use crate::{pool::{Heap, RcValue}, value::Value};
+use core::cmp::Ordering;
+
+#[cfg(feature = "bigint")]
+use {alloc::boxed::Box, num_bigint::{BigInt, Sign}, num_integer::Integer, num_traits::{FromPrimitive, ToPrimitive, Zero}};
+
+/// Splits a leading sign off `text`, saying whether it was a minus.
+fn sign(text: &str) -> (bool, &str) {
+    match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    }
+}
+
+/// Whether `text` is digits in `radix`, any two of which may be separated
+/// by an underscore.
+fn digits(text: &str, radix: u32) -> bool {
+    !text.is_empty() && text.split('_').all(|run| !run.is_empty() && run.chars().all(|c| c.is_digit(radix)))
+}
+
+/// Integers wide enough to hold the products in exact arithmetic on two
+/// `Integer`s or `Ratio`s.
+#[cfg(not(feature = "bigint"))]
+type Wide = i128;
+#[cfg(feature = "bigint")]
+type Wide = BigInt;
+
+/// A numeric `Value` taken out of its cell for arithmetic.
+///
+/// Operations on two numbers work in the wider of their kinds: integers
+/// and ratios are exact, and anything with a float gives a float. Dividing
+/// integers that do not divide evenly gives a `Ratio`. With the `bigint`
+/// feature, integer results that overflow `i64` become `Big`, and `Big`
+/// results that fit go back to `Integer`; without it, they fail.
+///
+/// A ratio whose terms in lowest form do not fit in an `i64` becomes a float.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    #[cfg(feature = "bigint")]
+    Big(BigInt),
+    /// A numerator and denominator in lowest terms, the denominator above one.
+    Ratio(i64, i64),
+    Float(f64),
+}
+
+/// Why an operation on numbers has no result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// An exact division by zero. Dividing a float by zero gives an
+    /// infinity or NaN instead.
+    DivisionByZero,
+    /// An integer too big for an `i64`, without the `bigint` feature.
+    Overflow,
+    /// An infinity or NaN where an integer is wanted.
+    NotFinite,
+}
+
+impl Failure {
+    /// The type of the error that reports it.
+    pub fn kind(self) -> &'static str {
+        match self {
+            Failure::DivisionByZero => "division-by-zero",
+            Failure::Overflow | Failure::NotFinite => "arithmetic-error",
+        }
+    }
+
+    /// The message of the error that reports it.
+    pub fn message(self) -> &'static str {
+        match self {
+            Failure::DivisionByZero => "division by zero",
+            Failure::Overflow => "integer overflow",
+            Failure::NotFinite => "not a finite number",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Number {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(n) => Some(Number::Integer(*n)),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(n) => Some(Number::Big((**n).clone())),
+            Value::Ratio(n, d) => Some(Number::Ratio(*n, *d)),
+            Value::Number(x) => Some(Number::Float(*x)),
+            _ => None
+        }
+    }
+
+    pub fn into_value<'s>(self, pool: &'s dyn Heap<'s>) -> RcValue<'s> {
+        match self.demote() {
+            Number::Integer(n) => pool.new_integer(n),
+            #[cfg(feature = "bigint")]
+            Number::Big(n) => pool.alloc(Value::BigInt(Box::new(n))).unwrap(),
+            Number::Ratio(n, d) => pool.alloc(Value::Ratio(n, d)).unwrap(),
+            Number::Float(x) => pool.new_number(x),
+        }
+    }
+
+    /// Reads `token` as a number, or gives `None` if it is not one, in which
+    /// case the reader takes it for a symbol. After an optional sign, a
+    /// number is one of:
+    ///
+    /// - an integer: decimal digits, or hexadecimal, binary or octal ones
+    ///   after `0x`, `0b` or `0o`. `#x`, `#b` and `#o` also work, with the
+    ///   sign after them, as in `#x-1f`;
+    /// - a ratio `n/d` of decimal integers, read in lowest terms, with `d`
+    ///   not zero;
+    /// - a float, with a point, an exponent or both: `1.`, `.5`, `1.5e3`,
+    ///   `1e3`, and `+inf.0`, `-inf.0` and `+nan.0`.
+    ///
+    /// Digits may be separated by single underscores, as in `1_000_000`.
+    /// So `-`, `+`, `.`, `1+`, `inf` and `1_` are all symbols. Without the
+    /// `bigint` feature, an integer too big for an `i64` reads as the
+    /// nearest float.
+    pub fn parse(token: &str) -> Option<Self> {
+        match token {
+            "+inf.0" => return Some(Number::Float(f64::INFINITY)),
+            "-inf.0" => return Some(Number::Float(f64::NEG_INFINITY)),
+            "+nan.0" | "-nan.0" => return Some(Number::Float(f64::NAN)),
+            _ => {},
+        }
+
+        let radix = |prefix: &str| match prefix {
+            "x" | "X" => Some(16),
+            "b" | "B" => Some(2),
+            "o" | "O" => Some(8),
+            _ => None,
+        };
+
+        if let Some(rest) = token.strip_prefix('#') {
+            let (negative, digits) = sign(rest.get(1..)?);
+            return Number::parse_digits(digits, radix(rest.get(..1)?)?, negative);
+        }
+
+        let (negative, unsigned) = sign(token);
+        if let Some(radix) = unsigned.strip_prefix('0').and_then(|rest| radix(rest.get(..1)?)) {
+            return Number::parse_digits(&unsigned[2..], radix, negative);
+        }
+
+        if let Some((numerator, denominator)) = unsigned.split_once('/') {
+            let numerator = Number::parse_digits(numerator, 10, negative)?;
+            let denominator = Number::parse_digits(denominator, 10, false)?;
+            if denominator.is_zero() {
+                return None;
+            }
+
+            return numerator.combine(denominator, Op::Div).ok();
+        }
+
+        if digits(unsigned, 10) {
+            return Number::parse_digits(unsigned, 10, negative);
+        }
+
+        Number::parse_float(token, unsigned)
+    }
+
+    /// Reads digits in `radix`, perhaps separated by underscores.
+    fn parse_digits(text: &str, radix: u32, negative: bool) -> Option<Self> {
+        if !digits(text, radix) {
+            return None;
+        }
+
+        let mut n: i64 = 0;
+        for digit in text.chars().filter_map(|c| c.to_digit(radix)) {
+            let next = n.checked_mul(radix as i64).and_then(|n| {
+                if negative { n.checked_sub(digit as i64) } else { n.checked_add(digit as i64) }
+            });
+            match next {
+                Some(next) => n = next,
+                None => return Some(Number::parse_wide(text, radix, negative)),
+            }
+        }
+
+        Some(Number::Integer(n))
+    }
+
+    /// Reads digits too many for an `i64`.
+    #[cfg(feature = "bigint")]
+    fn parse_wide(text: &str, radix: u32, negative: bool) -> Self {
+        let mut n = BigInt::zero();
+        for digit in text.chars().filter_map(|c| c.to_digit(radix)) {
+            n = n * radix + digit;
+        }
+
+        Number::Big(if negative { -n } else { n })
+    }
+
+    /// Reads digits too many for an `i64`, as the nearest float.
+    #[cfg(not(feature = "bigint"))]
+    fn parse_wide(text: &str, radix: u32, negative: bool) -> Self {
+        let mut x = 0.0;
+        for digit in text.chars().filter_map(|c| c.to_digit(radix)) {
+            x = x * radix as f64 + digit as f64;
+        }
+
+        Number::Float(if negative { -x } else { x })
+    }
+
+    /// Reads `token` as a float, given it without its sign as `unsigned`.
+    fn parse_float(token: &str, unsigned: &str) -> Option<Self> {
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(at) => (&unsigned[..at], Some(&unsigned[at + 1..])),
+            None => (unsigned, None),
+        };
+        let (whole, fraction) = match mantissa.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (mantissa, None),
+        };
+
+        let optional = |text: &str| text.is_empty() || digits(text, 10);
+        let valid = (fraction.is_some() || exponent.is_some())
+            && (digits(whole, 10) || whole.is_empty() && fraction.is_some_and(|fraction| digits(fraction, 10)))
+            && fraction.is_none_or(optional)
+            && exponent.is_none_or(|exponent| digits(sign(exponent).1, 10));
+        if !valid {
+            return None;
+        }
+
+        if !token.contains('_') {
+            return token.parse().ok().map(Number::Float);
+        }
+
+        // `f64::from_str` takes no separators, and without an allocator
+        // there is only so much room to take them out.
+        let mut buffer: heapless::String<64> = heapless::String::new();
+        for c in token.chars().filter(|c| *c != '_') {
+            buffer.push(c).ok()?;
+        }
+
+        buffer.parse().ok().map(Number::Float)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(n) => *n as f64,
+            #[cfg(feature = "bigint")]
+            Number::Big(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Ratio(n, d) => *n as f64 / *d as f64,
+            Number::Float(x) => *x,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Integer(n) => *n == 0,
+            #[cfg(feature = "bigint")]
+            Number::Big(n) => n.is_zero(),
+            Number::Ratio(_, _) => false,
+            Number::Float(x) => *x == 0.0,
+        }
+    }
+
+    /// The numerator of a rational number in lowest terms, or `None` for a float.
+    pub fn numerator(&self) -> Option<Self> {
+        match self {
+            Number::Ratio(n, _) => Some(Number::Integer(*n)),
+            Number::Float(_) => None,
+            integer => Some(integer.clone()),
+        }
+    }
+
+    /// The denominator of a rational number in lowest terms, or `None` for a float.
+    pub fn denominator(&self) -> Option<Self> {
+        match self {
+            Number::Ratio(_, d) => Some(Number::Integer(*d)),
+            Number::Float(_) => None,
+            _ => Some(Number::Integer(1)),
+        }
+    }
+
+    /// The greatest integer not above this number. Fails on a float that
+    /// is not finite, or without `bigint`, one too big for an `i64`.
+    pub fn floor(&self) -> Result<Self, Failure> {
+        match self {
+            Number::Ratio(n, d) => Ok(Number::Integer(n.div_euclid(*d))),
+            Number::Float(x) => Number::integral(libm::floor(*x)),
+            integer => Ok(integer.clone()),
+        }
+    }
+
+    /// The least integer not below this number. Fails like `floor`.
+    pub fn ceiling(&self) -> Result<Self, Failure> {
+        match self {
+            Number::Ratio(n, d) => Ok(Number::Integer(n.div_euclid(*d) + 1)),
+            Number::Float(x) => Number::integral(libm::ceil(*x)),
+            integer => Ok(integer.clone()),
+        }
+    }
+
+    /// This number with any fractional part dropped. Fails like `floor`.
+    pub fn truncate(&self) -> Result<Self, Failure> {
+        match self {
+            Number::Ratio(n, d) => Ok(Number::Integer(n / d)),
+            Number::Float(x) => Number::integral(libm::trunc(*x)),
+            integer => Ok(integer.clone()),
+        }
+    }
+
+    /// The nearest integer, or the even one of two equally near. Fails
+    /// like `floor`.
+    pub fn round(&self) -> Result<Self, Failure> {
+        match self {
+            Number::Ratio(n, d) => {
+                let (floor, rest) = (n.div_euclid(*d), n.rem_euclid(*d) as i128 * 2);
+                Ok(match rest.cmp(&(*d as i128)) {
+                    Ordering::Greater => Number::Integer(floor + 1),
+                    Ordering::Equal if floor % 2 != 0 => Number::Integer(floor + 1),
+                    _ => Number::Integer(floor),
+                })
+            },
+            Number::Float(x) => Number::integral(libm::roundeven(*x)),
+            integer => Ok(integer.clone()),
+        }
+    }
+
+    pub fn to_float(&self) -> Self {
+        Number::Float(self.to_f64())
+    }
+
+    /// Fails only without `bigint`, on the most negative `i64`.
+    pub fn abs(self) -> Result<Self, Failure> {
+        match self {
+            Number::Float(x) => Ok(Number::Float(libm::fabs(x))),
+            number if number.compare(&Number::Integer(0)) == Some(Ordering::Less) => Number::Integer(0).combine(number, Op::Sub),
+            number => Ok(number),
+        }
+    }
+
+    /// What is left of this number after taking away a multiple of
+    /// `divisor`, with the sign of `divisor`: the `mod` of `floor`.
+    pub fn modulo(self, divisor: Self) -> Result<Self, Failure> {
+        if let (Number::Float(_), _) | (_, Number::Float(_)) = (&self, &divisor) {
+            let (x, y) = (self.to_f64(), divisor.to_f64());
+            let rest = libm::fmod(x, y);
+            return Ok(Number::Float(if rest != 0.0 && (rest < 0.0) != (y < 0.0) { rest + y } else { rest }));
+        }
+
+        let quotient = self.clone().combine(divisor.clone(), Op::Div)?.floor()?;
+        self.combine(divisor.combine(quotient, Op::Mul)?, Op::Sub)
+    }
+
+    /// What is left of this number after taking away a multiple of
+    /// `divisor`, with the sign of this number: the `rem` of `truncate`.
+    pub fn remainder(self, divisor: Self) -> Result<Self, Failure> {
+        if let (Number::Float(_), _) | (_, Number::Float(_)) = (&self, &divisor) {
+            return Ok(Number::Float(libm::fmod(self.to_f64(), divisor.to_f64())));
+        }
+
+        let quotient = self.clone().combine(divisor.clone(), Op::Div)?.truncate()?;
+        self.combine(divisor.combine(quotient, Op::Mul)?, Op::Sub)
+    }
+
+    /// This number raised to `power`: exactly for a rational number to an
+    /// integer power, and as a float otherwise.
+    pub fn expt(self, power: Self) -> Result<Self, Failure> {
+        match (self, power) {
+            (Number::Float(x), power) => Ok(Number::Float(libm::pow(x, power.to_f64()))),
+            (mut base, Number::Integer(exponent)) => {
+                let mut result = Number::Integer(1);
+                let mut n = exponent.unsigned_abs();
+                while n > 0 {
+                    if n & 1 == 1 {
+                        result = result.combine(base.clone(), Op::Mul)?;
+                    }
+                    n >>= 1;
+                    if n > 0 {
+                        base = base.clone().combine(base, Op::Mul)?;
+                    }
+                }
+
+                if exponent < 0 { Number::Integer(1).combine(result, Op::Div) } else { Ok(result) }
+            },
+            (base, power) => Ok(Number::Float(libm::pow(base.to_f64(), power.to_f64()))),
+        }
+    }
+
+    /// `n` times two to the power of `count`, rounded down: bits shifted
+    /// left while keeping the sign, or right with the sign bit copied in.
+    /// A left shift past 64 bits gives a `Big` with `bigint`, and fails
+    /// without.
+    pub fn ash(n: i64, count: i64) -> Result<Self, Failure> {
+        if count < 0 {
+            return Ok(Number::Integer(n >> count.unsigned_abs().min(63)));
+        }
+        if n == 0 {
+            return Ok(Number::Integer(0));
+        }
+        if count < 64 && (n << count) >> count == n {
+            return Ok(Number::Integer(n << count));
+        }
+
+        #[cfg(feature = "bigint")]
+        return Ok(Number::Big(BigInt::from(n) << count as usize));
+        #[cfg(not(feature = "bigint"))]
+        Err(Failure::Overflow)
+    }
+
+    /// Orders two numbers by value, exactly unless either is a float. Only
+    /// a NaN is unordered.
+    // The references spare cloning a `BigInt`; an `i128` has no need of them.
+    #[allow(clippy::op_ref)]
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self.terms(), other.terms()) {
+            (Some((an, ad)), Some((bn, bd))) => Some((an * &bd).cmp(&(bn * &ad))),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    /// The numerator and positive denominator of a rational number.
+    fn terms(&self) -> Option<(Wide, Wide)> {
+        match self {
+            Number::Integer(n) => Some((Wide::from(*n), Wide::from(1))),
+            #[cfg(feature = "bigint")]
+            Number::Big(n) => Some((n.clone(), Wide::from(1))),
+            Number::Ratio(n, d) => Some((Wide::from(*n), Wide::from(*d))),
+            Number::Float(_) => None,
+        }
+    }
+
+    /// A float with no fractional part as an integer.
+    fn integral(x: f64) -> Result<Self, Failure> {
+        if (i64::MIN as f64..-(i64::MIN as f64)).contains(&x) {
+            return Ok(Number::Integer(x as i64));
+        }
+
+        #[cfg(feature = "bigint")]
+        if let Some(n) = BigInt::from_f64(x) {
+            return Ok(Number::Big(n));
+        }
+
+        Err(if x.is_finite() { Failure::Overflow } else { Failure::NotFinite })
+    }
+
+    /// `n / d` in lowest terms, as an integer if `d` divides `n`.
+    #[cfg(not(feature = "bigint"))]
+    fn reduce(n: Wide, d: Wide) -> Result<Self, Failure> {
+        if d == 0 {
+            return Err(Failure::DivisionByZero);
+        }
+
+        let (mut a, mut b) = (n.unsigned_abs(), d.unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let (n, d) = (n / a as i128, d / a as i128);
+        let (n, d) = if d < 0 { (-n, -d) } else { (n, d) };
+
+        match (i64::try_from(n), i64::try_from(d)) {
+            (Ok(n), Ok(1)) => Ok(Number::Integer(n)),
+            (Err(_), Ok(1)) => Err(Failure::Overflow),
+            (Ok(n), Ok(d)) => Ok(Number::Ratio(n, d)),
+            _ => Ok(Number::Float(n as f64 / d as f64)),
+        }
+    }
+
+    /// `n / d` in lowest terms, as an integer if `d` divides `n`.
+    #[cfg(feature = "bigint")]
+    fn reduce(n: Wide, d: Wide) -> Result<Self, Failure> {
+        if d.is_zero() {
+            return Err(Failure::DivisionByZero);
+        }
+
+        let gcd = n.gcd(&d);
+        let (n, d) = (n / &gcd, d / &gcd);
+        let (n, d) = if d.sign() == Sign::Minus { (-n, -d) } else { (n, d) };
+
+        Ok(match (n.to_i64(), d.to_i64()) {
+            (_, Some(1)) => Number::Big(n).demote(),
+            (Some(n), Some(d)) => Number::Ratio(n, d),
+            _ => Number::Float(n.to_f64().unwrap_or(f64::NAN) / d.to_f64().unwrap_or(f64::NAN)),
+        })
+    }
+
+    #[cfg(feature = "bigint")]
+    fn demote(self) -> Self {
+        match self {
+            Number::Big(n) => match n.to_i64() {
+                Some(n) => Number::Integer(n),
+                None => Number::Big(n),
+            },
+            number => number,
+        }
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn demote(self) -> Self {
+        self
+    }
+
+    /// Applies `op` to two integers directly when the result fits, to the
+    /// terms of two rational numbers when not, and to floats when either is
+    /// a float. Fails on an exact division by zero, or without `bigint`,
+    /// an integer result too big for an `i64`.
+    #[allow(clippy::op_ref)]
+    pub fn combine(self, other: Self, op: Op) -> Result<Self, Failure> {
+        if let (Number::Integer(a), Number::Integer(b)) = (&self, &other) {
+            let result = match op {
+                Op::Add => a.checked_add(*b),
+                Op::Sub => a.checked_sub(*b),
+                Op::Mul => a.checked_mul(*b),
+                Op::Div if a.checked_rem(*b) == Some(0) => a.checked_div(*b),
+                Op::Div => None,
+            };
+            if let Some(n) = result {
+                return Ok(Number::Integer(n));
+            }
+        }
+
+        let ((an, ad), (bn, bd)) = match (self.terms(), other.terms()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                let (a, b) = (self.to_f64(), other.to_f64());
+                return Ok(Number::Float(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                }));
+            },
+        };
+
+        match op {
+            Op::Add => Number::reduce(an * &bd + bn * &ad, ad * bd),
+            Op::Sub => Number::reduce(an * &bd - bn * &ad, ad * bd),
+            Op::Mul => Number::reduce(an * bn, ad * bd),
+            Op::Div => Number::reduce(an * bd, ad * bn),
+        }
+    }
+}
@@ -5,75 +5,216 @@ use nom::{
     branch::alt,
     bytes::complete as bytes,
     character::complete as character,
-    combinator::{not,peek,map},
+    combinator::{map_opt,opt,recognize,verify},
+    sequence::pair,
 };
 
-use crate::pool::{Pool, RcValue};
+use crate::{arena::{Bytes, Text}, builtins::vector_from_list, number::Number, pool::{Heap, RcValue}, value::{Value, CHAR_NAMES}};
 
-pub fn integer<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, result) = map(character::i64, |n| pool.new_integer(n))(input)?;
-    let (input, _) = not(peek(bytes::tag(".")))(input)?;
+/// What a parse makes values in, and how it makes a symbol of a name: by
+/// borrowing the name from the source, or by copying it.
+struct Reader<'r, 'i, 's> {
+    pool: &'s dyn Heap<'s>,
+    symbol: &'r dyn Fn(&'i str) -> Option<RcValue<'s>>,
+}
+
+/// The failure for a literal there is no room for in the heap, which stops
+/// the parse rather than letting another reading be tried.
+fn no_room(input: &str) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::TooLarge))
+}
+
+/// `#(a b c)`, read as a vector of the elements of the list.
+fn vector<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (rest, _) = bytes::tag("#")(input)?;
+    let (rest, list) = cons(reader, rest)?;
+
+    let vector = vector_from_list(reader.pool, &list).ok_or_else(|| no_room(input))?;
+    Ok((rest, vector))
+}
+
+/// A byte written in hexadecimal, as one or two digits.
+fn hex_byte(digits: &str) -> Option<u8> {
+    if digits.is_empty() || digits.len() > 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u8::from_str_radix(digits, 16).ok()
+}
 
-    Ok((input, result))
+/// `#u8(01 ff)`, read as a byte buffer of the bytes written in hexadecimal.
+fn byte_buffer<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (rest, _) = bytes::tag("#u8(")(input)?;
+    let (rest, body) = verify(bytes::take_till(|c| c == ')'), |body: &str| {
+        body.split_ascii_whitespace().all(|digits| hex_byte(digits).is_some())
+    })(rest)?;
+    let (rest, _) = bytes::tag(")")(rest)?;
+
+    let mut digits = body.split_ascii_whitespace();
+    let block = reader.pool.alloc_block(digits.clone().count()).ok_or_else(|| no_room(input))?;
+    let buffer = Bytes::from_fn(block, |_| hex_byte(digits.next().unwrap()).unwrap());
+
+    let buffer = reader.pool.alloc(Value::Bytes(buffer)).map_err(|_| no_room(input))?;
+    Ok((rest, buffer))
 }
 
-pub fn number<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    map(nom::number::complete::double, |x| pool.new_number(x))(input)
+/// The character `name` stands for after `#\`: itself if it is one
+/// character long, the character so named in `CHAR_NAMES`, or after an `x`
+/// the character with that hexadecimal code.
+fn named_char(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    match (chars.next()?, chars.as_str()) {
+        (c, "") => Some(c),
+        ('x' | 'X', code) if code.chars().all(|c| c.is_ascii_hexdigit()) => {
+            char::from_u32(u32::from_str_radix(code, 16).ok()?)
+        },
+        _ => CHAR_NAMES.iter().find(|(known, _)| known.eq_ignore_ascii_case(name)).map(|(_, c)| *c),
+    }
+}
+
+/// `#\a`, or a character by name, as in `#\space`, or by its code in
+/// hexadecimal, as in `#\x41`.
+fn character<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = bytes::tag("#\\")(input)?;
+    let (input, c) = map_opt(alt((token, recognize(character::anychar))), named_char)(input)?;
+
+    Ok((input, reader.pool.new_char(c)))
 }
 
-pub fn cons_end<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, _) = character::space0(input)?;
+/// A number, in any of the forms `Number::parse` reads.
+fn number<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, result) = map_opt(
+        alt((recognize(pair(bytes::tag("#"), token)), token)),
+        Number::parse
+    )(input)?;
+
+    Ok((input, result.into_value(reader.pool)))
+}
+
+fn cons_end<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = character::multispace0(input)?;
     let (input, _) = bytes::tag(")")(input)?;
-    Ok((input, pool.new_symbol("nil")))
+    Ok((input, reader.pool.new_symbol("nil")))
 }
 
-pub fn cons_pair<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, _) = character::space0(input)?;
-    let (input, _) = bytes::tag(".")(input)?;
-    let (input, _) = character::space0(input)?;
-    let (input, cdr) = parse(pool, input)?;
-    let (input, _) = character::space0(input)?;
+fn cons_pair<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = character::multispace0(input)?;
+    let (input, _) = verify(token, |token: &str| token == ".")(input)?;
+    let (input, _) = character::multispace0(input)?;
+    let (input, cdr) = datum(reader, input)?;
+    let (input, _) = character::multispace0(input)?;
     let (input, _) = bytes::tag(")")(input)?;
     Ok((input, cdr))
 }
 
-pub fn cons_rest<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
+fn cons_rest<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
     alt((
-        |input| cons_end(pool, input),
-        |input| cons_pair(pool, input),
+        |input| cons_end(reader, input),
+        |input| cons_pair(reader, input),
         |input| {
-            let (input, _) = character::space0(input)?;
-            let (input, car) = parse(pool, input)?;
-            let (input, cdr) = cons_rest(pool, input)?;
+            let (input, _) = character::multispace0(input)?;
+            let (input, car) = datum(reader, input)?;
+            let (input, cdr) = cons_rest(reader, input)?;
 
-            Ok((input, pool.new_cons(car, cdr)))
+            Ok((input, reader.pool.new_cons(car, cdr)))
         }
     ))(input)
 }
 
-pub fn cons<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, _) = character::space0(input)?;
+fn cons<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = character::multispace0(input)?;
     let (input, _) = bytes::tag("(")(input)?;
-    let (input, car) = parse(pool, input)?;
-    let (input, cdr) = cons_rest(pool, input)?;
+    if let Ok((input, nil)) = cons_end(reader, input) {
+        return Ok((input, nil));
+    }
+
+    let (input, car) = datum(reader, input)?;
+    let (input, cdr) = cons_rest(reader, input)?;
 
-    Ok((input, pool.new_cons(car, cdr)))
+    Ok((input, reader.pool.new_cons(car, cdr)))
 }
 
-pub fn symbol<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, _) = character::space0(input)?;
-    let (input, symbol) = input.split_at_position(
-        |c| !c.is_alphanum() && c != '+' && c != '-' && c != '*' && c != '/'
-    )?;
-    Ok((input, pool.new_symbol(symbol)))
+/// The bytes of a string literal's body, with its escapes resolved.
+fn unescape(body: &str) -> impl Iterator<Item = u8> + '_ {
+    let mut bytes = body.bytes();
+    core::iter::from_fn(move || match bytes.next()? {
+        b'\\' => match bytes.next()? {
+            b'n' => Some(b'\n'),
+            b't' => Some(b'\t'),
+            escaped => Some(escaped),
+        },
+        byte => Some(byte),
+    })
 }
 
-pub fn parse<'s, const N: usize>(pool: &'s Pool<'s, N>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
-    let (input, _) = character::space0(input)?;
+fn string<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (rest, _) = bytes::tag("\"")(input)?;
+    let (rest, body) = opt(bytes::escaped(character::none_of("\\\""), '\\', character::one_of("\\\"nt")))(rest)?;
+    let body = body.unwrap_or("");
+    let (rest, _) = bytes::tag("\"")(rest)?;
+
+    let mut block = reader.pool.alloc_block(unescape(body).count()).ok_or_else(|| no_room(input))?;
+    for (byte, unescaped) in block.bytes_mut().iter_mut().zip(unescape(body)) {
+        *byte = unescaped;
+    }
+
+    // Escapes only ever stand for ASCII, so the result is as valid as the source.
+    let string = reader.pool.alloc(Value::String(unsafe { Text::from_utf8_unchecked(block) })).map_err(|_| no_room(input))?;
+    Ok((rest, string))
+}
+
+/// Characters besides letters and digits that may appear in a symbol.
+const SYMBOL_PUNCTUATION: &str = "+-*/<>=!?&%_:.";
+
+/// A run of the characters that make up numbers and symbols.
+fn token(input: &str) -> IResult<&str, &str> {
+    input.split_at_position1_complete(
+        |c| !c.is_alphanum() && !SYMBOL_PUNCTUATION.contains(c),
+        nom::error::ErrorKind::Alpha
+    )
+}
+
+/// Any token that is not a number.
+fn symbol<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = character::multispace0(input)?;
+    let (rest, name) = verify(token, |token: &str| Number::parse(token).is_none())(input)?;
+
+    let symbol = (reader.symbol)(name).ok_or_else(|| no_room(input))?;
+    Ok((rest, symbol))
+}
+
+/// `'x`, read as `(quote x)`.
+fn quote<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = bytes::tag("'")(input)?;
+    let pool = reader.pool;
+    let (input, quoted) = datum(reader, input)?;
+
+    Ok((input, pool.new_cons(pool.new_symbol("quote"), pool.new_cons(quoted, pool.new_symbol("nil")))))
+}
+
+fn datum<'i, 's>(reader: &Reader<'_, 'i, 's>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    let (input, _) = character::multispace0(input)?;
     alt((
-        |input| cons(pool, input),
-        |input| integer(pool, input),
-        |input| number(pool, input),
-        |input| symbol(pool, input),
+        |input| cons(reader, input),
+        |input| quote(reader, input),
+        |input| byte_buffer(reader, input),
+        |input| vector(reader, input),
+        |input| character(reader, input),
+        |input| number(reader, input),
+        |input| string(reader, input),
+        |input| symbol(reader, input),
     ))(input)
 }
+
+/// Reads one datum from `input`. Its symbols borrow their names from
+/// `input`, which must outlive the pool's values.
+pub fn parse<'s>(pool: &'s dyn Heap<'s>, input: &'s str) -> IResult<&'s str, RcValue<'s>> {
+    datum(&Reader { pool, symbol: &|name| Some(pool.new_symbol(name)) }, input)
+}
+
+/// Reads one datum from `input`, copying the names of its symbols into the
+/// pool, so that `input` need not outlive them. Each name is freed with the
+/// last handle to its symbol.
+pub fn read<'i, 's>(pool: &'s dyn Heap<'s>, input: &'i str) -> IResult<&'i str, RcValue<'s>> {
+    datum(&Reader { pool, symbol: &|name| pool.new_named_symbol(name) }, input)
+}
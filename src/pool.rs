@@ -1,35 +1,639 @@
-use crate::value::Value;
+use crate::{arena::{Arena, Block, Bytes, Text, Vector}, table::Table, constants::{self, Immediate}, value::{Value, CONS, KINDS}};
 use core::mem::MaybeUninit;
-use core::cell::{Cell, UnsafeCell};
+use core::cell::UnsafeCell;
 use core::ops::Deref;
+use core::mem;
 use core::ptr;
 use core::fmt;
+use shared::{Counter, Slot};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+
+/// The few mutable words of pool bookkeeping: plain `Cell`s normally, and
+/// atomics under the `sync` feature so that a pool can be shared by threads.
+#[cfg(not(feature = "sync"))]
+pub(crate) mod shared {
+    use core::cell::Cell;
+
+    pub struct Counter(Cell<usize>);
+
+    impl Counter {
+        pub const fn new(n: usize) -> Self {
+            Counter(Cell::new(n))
+        }
+
+        pub fn get(&self) -> usize {
+            self.0.get()
+        }
+
+        pub fn set(&self, n: usize) {
+            self.0.set(n)
+        }
+
+        /// Adds one, returning the new count.
+        pub fn increment(&self) -> usize {
+            self.0.set(self.0.get().wrapping_add(1));
+            self.0.get()
+        }
+
+        /// Subtracts one, returning the new count.
+        pub fn decrement(&self) -> usize {
+            self.0.set(self.0.get() - 1);
+            self.0.get()
+        }
+
+        pub fn raise(&self, n: usize) {
+            self.0.set(self.0.get().max(n))
+        }
+
+        /// Adds one unless the count has reached `limit`, returning the old count.
+        pub fn claim(&self, limit: usize) -> Option<usize> {
+            let n = self.0.get();
+            if n >= limit {
+                return None;
+            }
+
+            self.0.set(n + 1);
+            Some(n)
+        }
+    }
+
+    pub struct Slot<T>(Cell<*const T>);
+
+    impl<T> Slot<T> {
+        pub const fn null() -> Self {
+            Slot(Cell::new(core::ptr::null()))
+        }
+
+        pub fn get(&self) -> *const T {
+            self.0.get()
+        }
+
+        pub fn set(&self, ptr: *const T) {
+            self.0.set(ptr)
+        }
+    }
+
+    /// Nothing can race without `sync`, so the lock only runs `f`.
+    pub struct Lock;
+
+    impl Lock {
+        pub const fn new() -> Self {
+            Lock
+        }
+
+        pub fn with<R>(&self, f: impl FnOnce() -> R) -> R {
+            f()
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+pub(crate) mod shared {
+    use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+    pub struct Counter(AtomicUsize);
+
+    impl Counter {
+        pub const fn new(n: usize) -> Self {
+            Counter(AtomicUsize::new(n))
+        }
+
+        pub fn get(&self) -> usize {
+            self.0.load(Ordering::Acquire)
+        }
+
+        pub fn set(&self, n: usize) {
+            self.0.store(n, Ordering::Release)
+        }
+
+        /// Adds one, returning the new count.
+        pub fn increment(&self) -> usize {
+            self.0.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+        }
+
+        /// Subtracts one, returning the new count. Orders everything this
+        /// thread did before the decrement before whoever sees it reach zero.
+        pub fn decrement(&self) -> usize {
+            self.0.fetch_sub(1, Ordering::AcqRel) - 1
+        }
+
+        pub fn raise(&self, n: usize) {
+            self.0.fetch_max(n, Ordering::Relaxed);
+        }
+
+        /// Adds one unless the count has reached `limit`, returning the old count.
+        pub fn claim(&self, limit: usize) -> Option<usize> {
+            self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < limit).then_some(n + 1)).ok()
+        }
+    }
+
+    pub struct Slot<T>(AtomicPtr<T>);
+
+    impl<T> Slot<T> {
+        pub const fn null() -> Self {
+            Slot(AtomicPtr::new(core::ptr::null_mut()))
+        }
+
+        pub fn get(&self) -> *const T {
+            self.0.load(Ordering::Acquire)
+        }
+
+        pub fn set(&self, ptr: *const T) {
+            self.0.store(ptr as *mut T, Ordering::Release)
+        }
+    }
+
+    /// A spin lock, for the little bookkeeping that is not lock-free.
+    pub struct Lock(AtomicBool);
+
+    impl Lock {
+        pub const fn new() -> Self {
+            Lock(AtomicBool::new(false))
+        }
+
+        pub fn with<R>(&self, f: impl FnOnce() -> R) -> R {
+            while self.0.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                core::hint::spin_loop();
+            }
+
+            let result = f();
+            self.0.store(false, Ordering::Release);
+            result
+        }
+    }
+}
 
 pub struct ValueCell<'s> {
     cell: UnsafeCell<MaybeUninit<Value<'s>>>,
-    rc: Cell<usize>
+    rc: Counter,
+    ledger: Slot<Ledger<'s>>,
+    /// The index plus one of the next free cell, while this one is free.
+    #[cfg(feature = "sync")]
+    next: core::sync::atomic::AtomicU32,
+    /// Bumped every time the cell is handed out, so that stale handles to
+    /// an earlier occupant can be told apart from current ones.
+    #[cfg(feature = "debug")]
+    generation: Counter,
 }
 
-pub struct RcValue<'s>(*const ValueCell<'s>);
+impl<'s> ValueCell<'s> {
+    const fn empty() -> Self {
+        ValueCell {
+            cell: UnsafeCell::new(MaybeUninit::uninit()),
+            rc: Counter::new(0),
+            ledger: Slot::null(),
+            #[cfg(feature = "sync")]
+            next: core::sync::atomic::AtomicU32::new(0),
+            #[cfg(feature = "debug")]
+            generation: Counter::new(0),
+        }
+    }
+}
+
+/// Bytes taken by each cell of a `Pool`.
+///
+/// A cell is a `Value` plus its reference count and a pointer back to the
+/// pool's bookkeeping. On 64-bit targets that is 40 bytes: 24 for the value
+/// and 8 for each word. The `sync` feature adds a 4-byte free-list index,
+/// padded to 48, and the `debug` feature a generation count and a wider
+/// `RcValue`, giving 64 (72 with both). On 32-bit targets whose `i64` is
+/// 8-aligned, such as ARMv7-M, a ratio's two `i64`s make the value 24 bytes
+/// and a cell 32, or 40 under `sync`.
+///
+/// A `Pool<N, BYTES>` is `N * CELL_SIZE` bytes for its cells and `BYTES`
+/// for its arena, plus a fixed header of counters.
+pub const CELL_SIZE: usize = mem::size_of::<ValueCell>();
+
+/// Bookkeeping shared by every cell of a pool. Cells point back at it so a
+/// release can be accounted for without a reference to the pool itself.
+///
+/// Released cells form an intrusive free list. Normally a dead cell's value
+/// storage holds the pointer to the next dead cell, and `free` is the head.
+struct Ledger<'s> {
+    #[cfg(not(feature = "sync"))]
+    free: Slot<ValueCell<'s>>,
+    /// Under `sync`, the head of the free list is a cell index plus one in
+    /// the low half and a tag, bumped by every pop, in the high half. The tag
+    /// keeps a pop from succeeding against a head that was popped and pushed
+    /// back in the meantime.
+    #[cfg(feature = "sync")]
+    free: core::sync::atomic::AtomicU64,
+    /// The pool's first cell, which free-list indices count from.
+    #[cfg(feature = "sync")]
+    cells: Slot<ValueCell<'s>>,
+    live: Counter,
+    high_water: Counter,
+    allocations: Counter,
+    failed_allocations: Counter,
+    kinds: [Counter; KINDS.len()],
+    /// Another ledger whose counters follow this one's, as a growable pool's
+    /// ledger follows each of its chunks. Its free list is not touched.
+    parent: Slot<Ledger<'s>>,
+}
+
+impl<'s> Ledger<'s> {
+    const fn new() -> Self {
+        Ledger {
+            #[cfg(not(feature = "sync"))]
+            free: Slot::null(),
+            #[cfg(feature = "sync")]
+            free: core::sync::atomic::AtomicU64::new(0),
+            #[cfg(feature = "sync")]
+            cells: Slot::null(),
+            live: Counter::new(0),
+            high_water: Counter::new(0),
+            allocations: Counter::new(0),
+            failed_allocations: Counter::new(0),
+            kinds: [const { Counter::new(0) }; KINDS.len()],
+            parent: Slot::null(),
+        }
+    }
+
+    fn acquired(&self, kind: usize) {
+        self.high_water.raise(self.live.increment());
+        self.allocations.increment();
+        self.kinds[kind].increment();
+
+        if let Some(parent) = unsafe { self.parent.get().as_ref() } {
+            parent.acquired(kind);
+        }
+    }
+
+    /// Returns a cell whose value has already been dropped to the free list.
+    fn release(&self, cell: &ValueCell<'s>, kind: usize) {
+        self.push(cell);
+        self.released(kind);
+    }
+
+    fn released(&self, kind: usize) {
+        self.live.decrement();
+        self.kinds[kind].decrement();
+
+        if let Some(parent) = unsafe { self.parent.get().as_ref() } {
+            parent.released(kind);
+        }
+    }
+
+    fn stats(&self, capacity: usize, arena_capacity: usize, arena_used: usize) -> Stats {
+        Stats {
+            capacity,
+            live: self.live.get(),
+            free: capacity - self.live.get(),
+            high_water: self.high_water.get(),
+            allocations: self.allocations.get(),
+            failed_allocations: self.failed_allocations.get(),
+            kinds: self.kinds.each_ref().map(Counter::get),
+            arena_capacity,
+            arena_used,
+        }
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn pop(&self) -> Option<&'s ValueCell<'s>> {
+        let cell = unsafe { self.free.get().as_ref()? };
+        self.free.set(self.next_free(cell));
+
+        Some(cell)
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn push(&self, cell: &ValueCell<'s>) {
+        unsafe {
+            (cell.cell.get() as *mut *const ValueCell<'s>).write(self.free.get());
+        }
+        self.free.set(cell);
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn first_free(&self) -> *const ValueCell<'s> {
+        self.free.get()
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn next_free(&self, cell: &ValueCell<'s>) -> *const ValueCell<'s> {
+        unsafe { *(cell.cell.get() as *const *const ValueCell<'s>) }
+    }
+
+    #[cfg(feature = "sync")]
+    fn pop(&self) -> Option<&'s ValueCell<'s>> {
+        use core::sync::atomic::Ordering;
+
+        let mut head = self.free.load(Ordering::Acquire);
+        loop {
+            let cell = unsafe { self.cell(head as u32).as_ref()? };
+            let tag = (head & !(u32::MAX as u64)).wrapping_add(1 << 32);
+            let next = tag | cell.next.load(Ordering::Relaxed) as u64;
+
+            match self.free.compare_exchange_weak(head, next, Ordering::Acquire, Ordering::Acquire) {
+                Ok(_) => return Some(cell),
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    #[cfg(feature = "sync")]
+    fn push(&self, cell: &ValueCell<'s>) {
+        use core::sync::atomic::Ordering;
+
+        let index = unsafe { (cell as *const ValueCell<'s>).offset_from(self.cells.get()) } as u64 + 1;
+        let mut head = self.free.load(Ordering::Relaxed);
+        loop {
+            cell.next.store(head as u32, Ordering::Relaxed);
+            let next = (head & !(u32::MAX as u64)) | index;
+
+            match self.free.compare_exchange_weak(head, next, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// The cell at a free-list index, which counts from one; zero is null.
+    #[cfg(feature = "sync")]
+    fn cell(&self, index: u32) -> *const ValueCell<'s> {
+        match index {
+            0 => ptr::null(),
+            index => self.cells.get().wrapping_add(index as usize - 1),
+        }
+    }
+
+    #[cfg(feature = "sync")]
+    fn first_free(&self) -> *const ValueCell<'s> {
+        self.cell(self.free.load(core::sync::atomic::Ordering::Acquire) as u32)
+    }
+
+    #[cfg(feature = "sync")]
+    fn next_free(&self, cell: &ValueCell<'s>) -> *const ValueCell<'s> {
+        self.cell(cell.next.load(core::sync::atomic::Ordering::Relaxed))
+    }
+}
+
+/// Set in the low bit of an `RcValue` that points at a static `Immediate`
+/// rather than a `ValueCell`. Both are at least 2-aligned, so the bit is free.
+const IMMEDIATE: usize = 1;
+
+/// Set in the next bit of an `RcValue` that points at a `Named` symbol.
+/// Blocks are 8-aligned, and so are cells and immediates, so it is free too.
+const NAMED: usize = 2;
+
+/// A symbol that owns its name, at the start of a block with the name's
+/// bytes after it. It has a count of its own, and the block is freed with
+/// the last handle, so it takes no cell.
+#[repr(C)]
+struct Named<'s> {
+    rc: Counter,
+    value: Value<'s>,
+}
+
+/// The name of a symbol that owns it, which lives only as long as the
+/// symbol does, and so is only lent out for as long as the value is
+/// borrowed.
+///
+/// ```compile_fail
+/// use myser::pool::{Heap, Pool};
+///
+/// let pool: Pool<'_, 16, 256> = Pool::new();
+/// let (_, symbol) = myser::parser::read(&pool, "name").unwrap();
+/// let name = symbol.name().unwrap();
+/// drop(symbol);
+/// pool.new_string("reuses the block").unwrap();
+/// assert_eq!(name, "name");
+/// ```
+pub struct NamedText {
+    ptr: *const u8,
+    len: usize,
+}
+
+impl NamedText {
+    pub fn as_str(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(self.ptr, self.len)) }
+    }
+}
+
+impl fmt::Debug for NamedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(feature = "sync")]
+unsafe impl Send for NamedText {}
+#[cfg(feature = "sync")]
+unsafe impl Sync for NamedText {}
+
+pub struct RcValue<'s> {
+    ptr: *const ValueCell<'s>,
+    /// The generation of the cell when this handle's value was allocated.
+    #[cfg(feature = "debug")]
+    generation: usize,
+}
+impl<'s> RcValue<'s> {
+    pub(crate) fn immediate(value: &'static Immediate) -> Self {
+        let ptr = value as *const Immediate as *const ValueCell<'s>;
+        RcValue {
+            ptr: ptr.map_addr(|addr| addr | IMMEDIATE),
+            #[cfg(feature = "debug")]
+            generation: 0,
+        }
+    }
+
+    /// A symbol named by a copy of `name`, in `block`, which has room for
+    /// a `Named` and the name after it.
+    fn named(block: Block<'s>, name: &str) -> Self {
+        let ptr = block.into_raw();
+        unsafe {
+            let text = ptr.add(mem::size_of::<Named>());
+            ptr::copy_nonoverlapping(name.as_ptr(), text, name.len());
+
+            // The name is freed with the block, and so with the symbol.
+            let name = NamedText { ptr: text, len: name.len() };
+            (ptr as *mut Named<'s>).write(Named { rc: Counter::new(1), value: Value::Named(name) });
+        }
+
+        RcValue {
+            ptr: (ptr as *const ValueCell<'s>).map_addr(|addr| addr | NAMED),
+            #[cfg(feature = "debug")]
+            generation: 0,
+        }
+    }
+
+    fn from_cell(cell: &ValueCell<'s>) -> Self {
+        RcValue {
+            ptr: cell,
+            #[cfg(feature = "debug")]
+            generation: cell.generation.get(),
+        }
+    }
+
+    /// The address of the value, which identifies it for as long as it lives.
+    pub fn addr(&self) -> usize {
+        self.ptr.addr()
+    }
+
+    pub fn is_immediate(&self) -> bool {
+        self.ptr.addr() & IMMEDIATE != 0
+    }
+
+    /// Whether this is a symbol that owns its name, made by
+    /// `Heap::new_named_symbol`.
+    pub fn is_named(&self) -> bool {
+        self.ptr.addr() & NAMED != 0
+    }
+
+    fn named_ref(&self) -> &Named<'s> {
+        unsafe { &*(self.ptr.map_addr(|addr| addr & !NAMED) as *const Named<'s>) }
+    }
+
+    /// The value, if this is the only handle to it, so that it can be changed
+    /// in place. Immediates are shared and never are.
+    pub(crate) fn get_mut(&mut self) -> Option<&mut Value<'s>> {
+        if self.is_immediate() || self.is_named() {
+            return None;
+        }
+
+        #[cfg(feature = "debug")]
+        self.check();
+
+        let inner = unsafe { &*self.ptr };
+        if inner.rc.get() != 1 {
+            return None;
+        }
+
+        Some(unsafe { (*inner.cell.get()).assume_init_mut() })
+    }
+
+    /// Panics if this handle's cell has been released or reused since.
+    #[cfg(feature = "debug")]
+    fn check(&self) {
+        let inner = unsafe { &*self.ptr };
+        if inner.generation.get() != self.generation || inner.rc.get() == 0 {
+            panic!("use after free of an RcValue");
+        }
+    }
+}
 impl<'s> Clone for RcValue<'s> {
     fn clone(&self) -> Self {
-        let inner = unsafe { &*self.0 };
-        inner.rc.set(inner.rc.get() + 1);
+        if self.is_immediate() {
+            return RcValue { ..*self };
+        }
+        if self.is_named() {
+            self.named_ref().rc.increment();
+            return RcValue { ..*self };
+        }
+
+        #[cfg(feature = "debug")]
+        self.check();
+
+        let inner = unsafe { &*self.ptr };
+        inner.rc.increment();
 
-        RcValue(self.0)
+        RcValue { ..*self }
     }
 }
 impl<'s> Drop for RcValue<'s> {
     fn drop(&mut self) {
-        unsafe {
-            let inner = &*self.0;
-            inner.rc.set(inner.rc.get() - 1);
+        if let Some(cell) = self.dying() {
+            unsafe { release(cell) }
+        }
+    }
+}
+impl<'s> RcValue<'s> {
+    /// Gives up this handle's count, returning the cell if it was the last.
+    fn dying(&self) -> Option<*const ValueCell<'s>> {
+        if self.is_immediate() {
+            return None;
+        }
+        if self.is_named() {
+            if self.named_ref().rc.decrement() == 0 {
+                // A symbol holds no `RcValue`s, so the block is all there is
+                // to free. The pointer is the one the block gave, rather than
+                // one made from `named_ref`, which may only reach the `Named`.
+                let block = self.ptr.map_addr(|addr| addr & !NAMED) as *mut u8;
+                drop(unsafe { Block::from_raw(block) });
+            }
+            return None;
+        }
+
+        let inner = unsafe { &*self.ptr };
+
+        #[cfg(feature = "debug")]
+        {
+            if inner.generation.get() != self.generation {
+                panic!("use after free of an RcValue");
+            }
+            if inner.rc.get() == 0 {
+                panic!("refcount underflow of an RcValue");
+            }
+        }
+
+        if inner.rc.decrement() > 0 {
+            return None;
+        }
+
+        Some(self.ptr)
+    }
+}
+
+#[cfg(feature = "sync")]
+unsafe impl<'s> Send for RcValue<'s> {}
+#[cfg(feature = "sync")]
+unsafe impl<'s> Sync for RcValue<'s> {}
+
+/// A cell whose value has been moved out but whose `cdr` is still to be
+/// released. It is written over the cell's own value storage.
+struct Deferred<'s> {
+    next: *const ValueCell<'s>,
+    cdr: RcValue<'s>,
+}
+
+const _: () = assert!(
+    mem::size_of::<Deferred>() <= mem::size_of::<Value>()
+        && mem::align_of::<Deferred>() <= mem::align_of::<Value>()
+);
 
-            if inner.rc.get() > 0 {
-                return;
+/// Drops the value of a cell whose count reached zero and returns the cell to
+/// its pool, along with every descendant that dies with it.
+///
+/// This runs in constant native stack: the `car` is released first, and the
+/// cons waits on a list threaded through its vacated storage until its `cdr`
+/// can follow, so long lists and deep trees are both freed in one loop.
+unsafe fn release<'s>(cell: *const ValueCell<'s>) {
+    let mut deferred: *const ValueCell<'s> = ptr::null();
+    let mut next = Some(cell);
+
+    loop {
+        let cell = match next {
+            Some(cell) => &*cell,
+            None => {
+                let Some(cell) = deferred.as_ref() else { return };
+                let Deferred { next: rest, cdr } = (cell.cell.get() as *const Deferred<'s>).read();
+                deferred = rest;
+                (*cell.ledger.get()).release(cell, CONS);
+
+                next = cdr.dying();
+                mem::forget(cdr);
+                continue;
             }
+        };
+
+        let value = (*cell.cell.get()).assume_init_read();
+        let kind = value.kind();
+
+        match value {
+            Value::Cons(car, cdr) => {
+                (cell.cell.get() as *mut Deferred<'s>).write(Deferred { next: deferred, cdr });
+                deferred = cell;
 
-            ptr::drop_in_place((&mut *inner.cell.get()).assume_init_mut());
+                next = car.dying();
+                mem::forget(car);
+            },
+            value => {
+                drop(value);
+                (*cell.ledger.get()).release(cell, kind);
+                next = None;
+            }
         }
     }
 }
@@ -37,7 +641,19 @@ impl<'s> Deref for RcValue<'s> {
     type Target = Value<'s>;
 
     fn deref(&self) -> &Self::Target {
-        unsafe { (*(*self.0).cell.get()).assume_init_ref() }
+        if self.is_immediate() {
+            // Immediates hold no `RcValue`s, so `'static` can stand in for `'s`.
+            let ptr = self.ptr.map_addr(|addr| addr & !IMMEDIATE) as *const Value<'s>;
+            return unsafe { &*ptr };
+        }
+        if self.is_named() {
+            return &self.named_ref().value;
+        }
+
+        #[cfg(feature = "debug")]
+        self.check();
+
+        unsafe { (*(*self.ptr).cell.get()).assume_init_ref() }
     }
 }
 impl<'s> fmt::Debug for RcValue<'s> {
@@ -47,78 +663,457 @@ impl<'s> fmt::Debug for RcValue<'s> {
 }
 
 
-pub struct Pool<'s, const N: usize> {
+/// Where values are allocated: a fixed `Pool`, or with the `alloc` feature a
+/// `GrowablePool`. Both hand out the same `RcValue`s.
+///
+/// The evaluator, parser and builtins take a `&dyn Heap`, so one set of
+/// builtins serves pools of any size.
+pub trait Heap<'s> {
+    /// Allocates a cell for `value`, handing the value back if the heap is full.
+    fn alloc(&'s self, value: Value<'s>) -> Result<RcValue<'s>, Value<'s>>;
+
+    /// Allocates a zeroed block of `len` bytes for the contents of a value
+    /// too big for a cell, or `None` if there is no room.
+    fn alloc_block(&'s self, len: usize) -> Option<Block<'s>>;
+
+    fn stats(&self) -> Stats;
+
+    fn new_integer(&'s self, n: i64) -> RcValue<'s> {
+        match constants::small_integer(n) {
+            Some(immediate) => RcValue::immediate(immediate),
+            None => self.alloc(Value::Integer(n)).unwrap()
+        }
+    }
+
+    fn new_char(&'s self, c: char) -> RcValue<'s> {
+        match constants::ascii_char(c) {
+            Some(immediate) => RcValue::immediate(immediate),
+            None => self.alloc(Value::Char(c)).unwrap()
+        }
+    }
+
+    fn new_number(&'s self, x: f64) -> RcValue<'s> {
+        self.alloc(Value::Number(x)).unwrap()
+    }
+
+    fn new_symbol(&'s self, symbol: &'s str) -> RcValue<'s> {
+        match symbol {
+            "nil" => RcValue::immediate(&constants::NIL),
+            "t" => RcValue::immediate(&constants::T),
+            _ => self.alloc(Value::Symbol(symbol)).unwrap()
+        }
+    }
+
+    fn new_cons(&'s self, car: RcValue<'s>, cdr: RcValue<'s>) -> RcValue<'s> {
+        self.alloc(Value::Cons(car, cdr)).unwrap()
+    }
+
+    /// A string value holding a copy of `text`, or `None` if there is no
+    /// room for it.
+    fn new_string(&'s self, text: &str) -> Option<RcValue<'s>> {
+        let mut block = self.alloc_block(text.len())?;
+        block.bytes_mut().copy_from_slice(text.as_bytes());
+
+        self.alloc(Value::String(unsafe { Text::from_utf8_unchecked(block) })).ok()
+    }
+
+    /// A vector value of `len` elements, made by `fill` from each index in
+    /// order, or `None` if there is no room for it.
+    fn new_vector(&'s self, len: usize, fill: &mut dyn FnMut(usize) -> RcValue<'s>) -> Option<RcValue<'s>> {
+        let block = self.alloc_block(Vector::size(len)?)?;
+
+        self.alloc(Value::Vector(Vector::from_fn(block, fill))).ok()
+    }
+
+    /// A byte buffer of `len` bytes, made by `fill` from each index in
+    /// order, or `None` if there is no room for it.
+    fn new_bytes(&'s self, len: usize, fill: &mut dyn FnMut(usize) -> u8) -> Option<RcValue<'s>> {
+        let block = self.alloc_block(len)?;
+
+        self.alloc(Value::Bytes(Bytes::from_fn(block, fill))).ok()
+    }
+
+    /// An empty hash table with room for `capacity` entries, or more if it
+    /// is `growable`, or `None` if there is no room for it.
+    fn new_table(&'s self, capacity: usize, growable: bool) -> Option<RcValue<'s>> {
+        let block = self.alloc_block(Table::size(capacity)?)?;
+
+        self.alloc(Value::Table(Table::new(block, capacity, growable))).ok()
+    }
+
+    /// A symbol named by a copy of `name`, for names that do not live as
+    /// long as the heap, or `None` if there is no room for the copy. The
+    /// copy is freed with the last handle to the symbol.
+    fn new_named_symbol(&'s self, name: &str) -> Option<RcValue<'s>> {
+        match name {
+            "nil" => Some(RcValue::immediate(&constants::NIL)),
+            "t" => Some(RcValue::immediate(&constants::T)),
+            _ => Some(RcValue::named(self.alloc_block(mem::size_of::<Named>() + name.len())?, name)),
+        }
+    }
+}
+
+/// A snapshot of a pool's occupancy, as returned by `Heap::stats`.
+///
+/// `kinds` counts the live cells holding each `Value` variant, indexed like
+/// `value::KINDS`. Immediates never occupy a cell and are not counted, nor
+/// are symbols that own their names, which are kept in blocks instead.
+///
+/// `arena_used` counts the bytes of blocks in use, headers included. A
+/// growable pool's blocks come from the global allocator, so its
+/// `arena_capacity` is whatever is in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub capacity: usize,
+    pub live: usize,
+    pub free: usize,
+    pub high_water: usize,
+    pub allocations: usize,
+    pub failed_allocations: usize,
+    pub kinds: [usize; KINDS.len()],
+    pub arena_capacity: usize,
+    pub arena_used: usize,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "capacity: {}", self.capacity)?;
+        writeln!(f, "live: {}", self.live)?;
+        writeln!(f, "free: {}", self.free)?;
+        writeln!(f, "high-water: {}", self.high_water)?;
+        writeln!(f, "allocations: {}", self.allocations)?;
+        writeln!(f, "failed-allocations: {}", self.failed_allocations)?;
+        writeln!(f, "arena-capacity: {}", self.arena_capacity)?;
+        writeln!(f, "arena-used: {}", self.arena_used)?;
+        for (kind, count) in KINDS.iter().zip(self.kinds.iter()) {
+            writeln!(f, "{}: {}", kind, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A fixed arena of `N` cells, `CELL_SIZE` bytes each. An all-zero `Pool`
+/// is a valid, empty pool, and `Pool::new` is a `const fn`, so a pool can be
+/// placed in a `static`; see `StaticPool`.
+///
+/// Strings, vectors, hash tables and byte buffers keep their contents in an
+/// arena of `BYTES` bytes beside the cells. It defaults to none at all, for
+/// pools that only ever hold numbers, symbols and lists; a pool that reads
+/// or makes any of the others needs room for them, as in `Pool<1000, 8192>`.
+/// Without it, reading a string literal fails and making one panics.
+pub struct Pool<'s, const N: usize, const BYTES: usize = 0> {
     pool: [ValueCell<'s>; N],
-    alloced: UnsafeCell<usize>,
+    /// Cells from `fresh` onwards have never been handed out.
+    fresh: Counter,
+    ledger: Ledger<'s>,
+    /// Where values too big for a cell, like strings, keep their contents.
+    arena: Arena<BYTES>,
 }
 
-unsafe impl<'s, const N: usize> Sync for Pool<'s, N> {}
+#[cfg(feature = "sync")]
+unsafe impl<'s, const N: usize, const BYTES: usize> Sync for Pool<'s, N, BYTES> {}
 
-impl<'s, const N: usize> Pool<'s, N> {
-    pub fn new() -> Self {
+/// With the `sync` feature every `Pool` can be shared between threads, and
+/// values allocated in it sent between them.
+#[cfg(feature = "sync")]
+pub type SyncPool<'s, const N: usize, const BYTES: usize = 0> = Pool<'s, N, BYTES>;
+
+impl<'s, const N: usize, const BYTES: usize> Default for Pool<'s, N, BYTES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s, const N: usize, const BYTES: usize> Pool<'s, N, BYTES> {
+    pub const fn new() -> Self {
         Pool {
-            pool: unsafe { MaybeUninit::zeroed().assume_init() },
-            alloced: UnsafeCell::new(0)
+            pool: [const { ValueCell::empty() }; N],
+            fresh: Counter::new(0),
+            ledger: Ledger::new(),
+            arena: Arena::new(),
         }
     }
 
-    fn alloc(&self, value: Value<'s>) -> Result<RcValue<'s>, Value<'s>> {
-        let n: usize = unsafe {
-            self.alloced.get().write(*self.alloced.get() + 1);
-            *self.alloced.get() % N
-        };
+    /// Takes a free cell, if there is one, without counting a failure.
+    fn claim(&'s self) -> Option<&'s ValueCell<'s>> {
+        #[cfg(feature = "sync")]
+        self.ledger.cells.set(self.pool.as_ptr());
 
-        for cell in self.pool[n..N].iter() {
-            if cell.rc.get() > 0 { continue; }
+        match self.ledger.pop() {
+            Some(cell) => Some(cell),
+            None => self.fresh.claim(N).map(|n| &self.pool[n]),
+        }
+    }
 
-            cell.rc.set(1);
-            unsafe {
-                cell.cell.get().write(MaybeUninit::new(value));
+    fn fill(&'s self, cell: &'s ValueCell<'s>, value: Value<'s>) -> RcValue<'s> {
+        #[cfg(feature = "debug")]
+        {
+            if cell.rc.get() != 0 {
+                panic!("free list holds a live cell");
             }
+            cell.generation.increment();
+        }
 
-            return Ok(RcValue(cell));
+        let kind = value.kind();
+        cell.rc.set(1);
+        cell.ledger.set(&self.ledger);
+        unsafe {
+            cell.cell.get().write(MaybeUninit::new(value));
         }
+        self.ledger.acquired(kind);
+
+        RcValue::from_cell(cell)
+    }
 
-        for cell in self.pool[0..n].iter() {
-            if cell.rc.get() > 0 { continue; }
+    /// Checks the pool's bookkeeping against the cells themselves, and the
+    /// arena's free list against its bounds. Under
+    /// `sync` the answer is only meaningful while no other thread is using
+    /// the pool.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        let fresh = self.fresh.get();
+        if fresh > N {
+            return Err("more cells handed out than the pool holds");
+        }
+        if self.pool[fresh..].iter().any(|cell| cell.rc.get() != 0) {
+            return Err("a cell that was never handed out is live");
+        }
 
-            cell.rc.set(1);
-            unsafe {
-                cell.cell.get().write(MaybeUninit::new(value));
+        let cells = self.pool[..fresh].as_ptr_range();
+        let mut free = 0;
+        let mut next = self.ledger.first_free();
+        while !next.is_null() {
+            if !cells.contains(&next) {
+                return Err("free list leaves the pool");
             }
+            if free == fresh {
+                return Err("free list is cyclic");
+            }
+
+            let cell = unsafe { &*next };
+            if cell.rc.get() != 0 {
+                return Err("free list holds a live cell");
+            }
+
+            free += 1;
+            next = self.ledger.next_free(cell);
+        }
 
-            return Ok(RcValue(cell));
+        let mut live = 0;
+        for cell in self.pool[..fresh].iter() {
+            if cell.rc.get() == 0 { continue; }
+
+            if !ptr::eq(cell.ledger.get(), &self.ledger) {
+                return Err("a live cell belongs to another pool");
+            }
+            live += 1;
+        }
+
+        if live != self.ledger.live.get() {
+            return Err("live count disagrees with the cells");
+        }
+        if live + free != fresh {
+            return Err("a dead cell is missing from the free list");
         }
-        Err(value)
+        if self.ledger.high_water.get() < live || self.ledger.allocations.get() < self.ledger.high_water.get() {
+            return Err("high-water mark is inconsistent");
+        }
+
+        self.arena.verify()
     }
+}
 
-    pub fn new_integer(&self, n: i64) -> RcValue<'s> {
-        match n {
-            // 0 => &ZERO,
-            _ => self.alloc(Value::Integer(n)).unwrap()
+impl<'s, const N: usize, const BYTES: usize> Heap<'s> for Pool<'s, N, BYTES> {
+    fn alloc(&'s self, value: Value<'s>) -> Result<RcValue<'s>, Value<'s>> {
+        match self.claim() {
+            Some(cell) => Ok(self.fill(cell, value)),
+            None => {
+                self.ledger.failed_allocations.increment();
+                Err(value)
+            }
         }
     }
 
-    pub fn new_number(&self, x: f64) -> RcValue<'s> {
-        self.alloc(Value::Number(x)).unwrap()
+    fn alloc_block(&'s self, len: usize) -> Option<Block<'s>> {
+        self.arena.alloc(len)
     }
 
-    pub fn new_symbol(&self, symbol: &'s str) -> RcValue<'s> {
-        match symbol {
-            // "+" => &PLUS_SYM,
-            // "-" => &MINUS_SYM,
-            // "nil" => &NIL_SYM,
-            // "while" => &WHILE_SYM,
-            // "quote" => &QUOTE_SYM,
-            _ => self.alloc(Value::Symbol(symbol)).unwrap()
+    fn stats(&self) -> Stats {
+        self.ledger.stats(N, BYTES, self.arena.used())
+    }
+}
+
+/// A `Pool` to keep in a `static`, so that it lives in `.bss` rather than on
+/// the stack.
+///
+/// A pool that is not `Sync` must only ever be used from one thread, so the
+/// `static` hands it out once: the first `take` gets the pool, with the
+/// `'static` lifetime its values borrow, and later calls get `None`.
+///
+/// ```
+/// use myser::pool::StaticPool;
+///
+/// static POOL: StaticPool<1000> = StaticPool::new();
+///
+/// let pool = POOL.take().unwrap();
+/// assert!(POOL.take().is_none());
+/// # let _ = pool;
+/// ```
+#[cfg(target_has_atomic = "8")]
+pub struct StaticPool<const N: usize, const BYTES: usize = 0> {
+    taken: core::sync::atomic::AtomicBool,
+    pool: Pool<'static, N, BYTES>,
+}
+
+// The pool is reachable only through `take`, which hands it out once.
+#[cfg(target_has_atomic = "8")]
+unsafe impl<const N: usize, const BYTES: usize> Sync for StaticPool<N, BYTES> {}
+
+#[cfg(target_has_atomic = "8")]
+impl<const N: usize, const BYTES: usize> Default for StaticPool<N, BYTES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const N: usize, const BYTES: usize> StaticPool<N, BYTES> {
+    pub const fn new() -> Self {
+        StaticPool {
+            taken: core::sync::atomic::AtomicBool::new(false),
+            pool: Pool::new(),
         }
     }
 
-    pub fn new_cons(&self, car: RcValue<'s>, cdr: RcValue<'s>) -> RcValue<'s> {
-        self.alloc(Value::Cons(car, cdr)).unwrap()
+    /// The pool, the first time this is called, and `None` after that.
+    pub fn take(&'static self) -> Option<&'static Pool<'static, N, BYTES>> {
+        if self.taken.swap(true, core::sync::atomic::Ordering::AcqRel) {
+            return None;
+        }
+
+        Some(&self.pool)
     }
+}
+
+/// Cells per chunk of a `GrowablePool`.
+#[cfg(feature = "alloc")]
+pub const CHUNK: usize = 1024;
 
-    // pub fn used(&self) -> usize {
-    //     self.alloced.load(Ordering::Acquire)
-    // }
+/// A pool that allocates chunks of `CHUNK` cells from the global allocator as
+/// it fills, optionally up to a limit. Chunks are kept until the pool is
+/// dropped. Each chunk is a `Pool` of its own whose counters roll up into the
+/// growable pool's ledger.
+#[cfg(feature = "alloc")]
+pub struct GrowablePool<'s> {
+    chunks: UnsafeCell<Vec<Box<Pool<'s, CHUNK>>>>,
+    /// The chunk that last had a free cell, where the next search starts.
+    current: core::cell::Cell<usize>,
+    /// The most chunks the pool may grow to, if bounded.
+    limit: Option<usize>,
+    ledger: Ledger<'s>,
+    /// Accounts for blocks, which come straight from the global allocator.
+    arena: crate::arena::Ledger,
+}
+
+#[cfg(feature = "alloc")]
+impl<'s> Default for GrowablePool<'s> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'s> GrowablePool<'s> {
+    /// A pool that grows without bound.
+    pub fn new() -> Self {
+        Self::bounded(None)
+    }
+
+    /// A pool that grows to at least `cells` cells, rounded up to whole chunks.
+    pub fn with_limit(cells: usize) -> Self {
+        Self::bounded(Some(cells.div_ceil(CHUNK)))
+    }
+
+    fn bounded(limit: Option<usize>) -> Self {
+        GrowablePool {
+            chunks: UnsafeCell::new(Vec::new()),
+            current: core::cell::Cell::new(0),
+            limit,
+            ledger: Ledger::new(),
+            arena: crate::arena::Ledger::global(),
+        }
+    }
+
+    fn chunks(&self) -> &[Box<Pool<'s, CHUNK>>] {
+        unsafe { &*self.chunks.get() }
+    }
+
+    fn grow(&'s self) -> Option<&'s Pool<'s, CHUNK>> {
+        if self.limit.is_some_and(|limit| self.chunks().len() >= limit) {
+            return None;
+        }
+
+        // An all-zero `Pool` is an empty pool.
+        let chunk: Box<Pool<'s, CHUNK>> = unsafe { Box::new_zeroed().assume_init() };
+        chunk.ledger.parent.set(&self.ledger);
+
+        let chunks = unsafe { &mut *self.chunks.get() };
+        chunks.push(chunk);
+        self.current.set(chunks.len() - 1);
+
+        Some(self.chunk(chunks.len() - 1))
+    }
+
+    fn chunk(&'s self, n: usize) -> &'s Pool<'s, CHUNK> {
+        // Chunks never move or go away while the pool is borrowed.
+        unsafe { &*(&*self.chunks()[n] as *const Pool<'s, CHUNK>) }
+    }
+
+    /// Checks every chunk, and that their counts add up to the pool's.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        let mut live = 0;
+        for chunk in self.chunks() {
+            chunk.verify()?;
+            live += chunk.ledger.live.get();
+        }
+
+        if live != self.ledger.live.get() {
+            return Err("live count disagrees with the chunks");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'s> Heap<'s> for GrowablePool<'s> {
+    fn alloc(&'s self, value: Value<'s>) -> Result<RcValue<'s>, Value<'s>> {
+        let chunks = self.chunks();
+        let current = self.current.get();
+
+        for n in (current..chunks.len()).chain(0..current) {
+            let chunk = self.chunk(n);
+            if let Some(cell) = chunk.claim() {
+                self.current.set(n);
+                return Ok(chunk.fill(cell, value));
+            }
+        }
+
+        match self.grow() {
+            Some(chunk) => Ok(chunk.fill(chunk.claim().unwrap(), value)),
+            None => {
+                self.ledger.failed_allocations.increment();
+                Err(value)
+            }
+        }
+    }
+
+    fn alloc_block(&'s self, len: usize) -> Option<Block<'s>> {
+        crate::arena::alloc_global(&self.arena, len)
+    }
+
+    fn stats(&self) -> Stats {
+        let used = self.arena.used();
+        self.ledger.stats(self.chunks().len() * CHUNK, used, used)
+    }
 }
@@ -0,0 +1,267 @@
+use crate::{arena::Block, pool::{shared::Lock, RcValue}, value::{equal, Value}};
+use core::cell::UnsafeCell;
+use core::ops::Deref;
+use core::{fmt, mem, ptr};
+
+/// The start of a table's block, before its slots.
+struct Header {
+    len: usize,
+    capacity: usize,
+}
+
+type Slot<'s> = Option<(RcValue<'s>, RcValue<'s>)>;
+
+/// A hash table value: keys and values in open-addressed slots of a `Block`,
+/// found by linear probing. Keys are compared with `value::equal`.
+///
+/// A table holds up to its capacity of entries, with a quarter or more of
+/// its slots left empty. A fixed table refuses entries past that; a
+/// growable one takes a block twice the size and moves into it.
+pub struct Table<'s> {
+    block: UnsafeCell<Block<'s>>,
+    lock: Lock,
+    growable: bool,
+}
+
+#[cfg(feature = "sync")]
+unsafe impl<'s> Sync for Table<'s> {}
+
+/// Slots for `capacity` entries.
+fn slots(capacity: usize) -> Option<usize> {
+    capacity.checked_add(capacity / 3 + 1)?.checked_next_power_of_two()
+}
+
+impl<'s> Table<'s> {
+    /// Bytes in a block for a table of `capacity` entries.
+    pub fn size(capacity: usize) -> Option<usize> {
+        slots(capacity)?.checked_mul(mem::size_of::<Slot>())?.checked_add(mem::size_of::<Header>())
+    }
+
+    /// An empty table in `block`, of `size(capacity)` bytes.
+    pub fn new(block: Block<'s>, capacity: usize, growable: bool) -> Self {
+        let table = Table { block: UnsafeCell::new(block), lock: Lock::new(), growable };
+        unsafe { table.format(capacity) };
+
+        table
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock.with(|| unsafe { (*self.header()).len })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock.with(|| unsafe { (*self.header()).capacity })
+    }
+
+    pub fn is_growable(&self) -> bool {
+        self.growable
+    }
+
+    /// Writes an empty header and slots over the block.
+    unsafe fn format(&self, capacity: usize) {
+        self.header().write(Header { len: 0, capacity });
+        let (slots, count) = self.slots();
+        for index in 0..count {
+            slots.add(index).write(None);
+        }
+    }
+
+    fn header(&self) -> *mut Header {
+        unsafe { (*self.block.get()).as_ptr() as *mut Header }
+    }
+
+    fn slots(&self) -> (*mut Slot<'s>, usize) {
+        let block = unsafe { &*self.block.get() };
+        let count = (block.len() - mem::size_of::<Header>()) / mem::size_of::<Slot>();
+        let slots = unsafe { block.as_ptr().add(mem::size_of::<Header>()) as *mut Slot<'s> };
+
+        (slots, count)
+    }
+
+    /// The slot holding `key`, or the empty slot where it would go. Must be
+    /// called under the lock.
+    unsafe fn find(&self, key: &Value<'s>) -> (*mut Slot<'s>, bool) {
+        let (slots, count) = self.slots();
+        let mut index = hash(key) as usize & (count - 1);
+        loop {
+            let slot = slots.add(index);
+            match &*slot {
+                Some((known, _)) if equal(known, key) => return (slot, true),
+                Some(_) => index = (index + 1) & (count - 1),
+                None => return (slot, false),
+            }
+        }
+    }
+
+    pub fn get(&self, key: &Value<'s>) -> Option<RcValue<'s>> {
+        self.lock.with(|| unsafe {
+            match self.find(key) {
+                (slot, true) => (*slot).as_ref().map(|(_, value)| value.clone()),
+                _ => None,
+            }
+        })
+    }
+
+    /// Binds `key` to `value`, returning what it was bound to before, or
+    /// handing both back if the table is full.
+    #[allow(clippy::type_complexity)]
+    pub fn insert(&self, key: RcValue<'s>, value: RcValue<'s>) -> Result<Option<RcValue<'s>>, (RcValue<'s>, RcValue<'s>)> {
+        self.lock.with(|| unsafe {
+            let header = &mut *self.header();
+            match self.find(&key) {
+                (slot, true) => Ok((*slot).as_mut().map(|(_, old)| mem::replace(old, value))),
+                _ if header.len == header.capacity => Err((key, value)),
+                (slot, false) => {
+                    slot.write(Some((key, value)));
+                    header.len += 1;
+                    Ok(None)
+                },
+            }
+        })
+    }
+
+    /// Unbinds `key`, returning what it was bound to.
+    pub fn remove(&self, key: &Value<'s>) -> Option<RcValue<'s>> {
+        let removed = self.lock.with(|| unsafe {
+            let (slot, found) = self.find(key);
+            if !found {
+                return None;
+            }
+
+            let removed = slot.replace(None);
+            (*self.header()).len -= 1;
+
+            // Move back the entries after the hole that probed past it, so
+            // that probing never stops short of them.
+            let (slots, count) = self.slots();
+            let mut hole = slot.offset_from(slots) as usize;
+            let mut index = (hole + 1) & (count - 1);
+            while let Some((key, _)) = &*slots.add(index) {
+                let home = hash(key) as usize & (count - 1);
+                if (index.wrapping_sub(home) & (count - 1)) >= (index.wrapping_sub(hole) & (count - 1)) {
+                    slots.add(hole).write(slots.add(index).replace(None));
+                    hole = index;
+                }
+                index = (index + 1) & (count - 1);
+            }
+
+            removed
+        });
+
+        removed.map(|(_, value)| value)
+    }
+
+    /// Moves the entries into `block`, of `size(capacity)` bytes, unless the
+    /// table already has room for as many.
+    pub fn grow(&self, block: Block<'s>, capacity: usize) {
+        let old = self.lock.with(|| unsafe {
+            if (*self.header()).capacity >= capacity {
+                return block;
+            }
+
+            let old = mem::replace(&mut *self.block.get(), block);
+            let len = (*(old.as_ptr() as *const Header)).len;
+            self.format(capacity);
+            (*self.header()).len = len;
+
+            let slots = old.as_ptr().add(mem::size_of::<Header>()) as *const Slot<'s>;
+            let count = (old.len() - mem::size_of::<Header>()) / mem::size_of::<Slot>();
+            for index in 0..count {
+                if let Some((key, value)) = slots.add(index).read() {
+                    let (slot, _) = self.find(&key);
+                    slot.write(Some((key, value)));
+                }
+            }
+
+            old
+        });
+
+        // Its entries were moved out, so this only frees the memory.
+        drop(old);
+    }
+
+    /// Calls `f` with each entry in turn, in no particular order. The table
+    /// is not held meanwhile, so `f` may change it, though entries it adds
+    /// or removes may throw off which of the rest are seen.
+    pub fn for_each(&self, mut f: impl FnMut(RcValue<'s>, RcValue<'s>)) {
+        let mut index = 0;
+        while let Some(entry) = self.lock.with(|| unsafe {
+            let (slots, count) = self.slots();
+            (index..count).find_map(|at| {
+                index = at + 1;
+                (*slots.add(at)).clone()
+            })
+        }) {
+            let (key, value) = entry;
+            f(key, value);
+        }
+    }
+}
+
+impl<'s> Drop for Table<'s> {
+    fn drop(&mut self) {
+        let (slots, count) = self.slots();
+        for index in 0..count {
+            unsafe { ptr::drop_in_place(slots.add(index)) }
+        }
+    }
+}
+
+impl<'s> fmt::Debug for Table<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        self.for_each(|key, value| { map.entry(&key, &value); });
+        map.finish()
+    }
+}
+
+/// FNV-1a, over the bytes that make two values `equal`.
+pub fn hash(value: &Value) -> u64 {
+    let mut state = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            state = (state ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    };
+
+    let mut value = value;
+    loop {
+        feed(&[value.kind() as u8]);
+        match value {
+            Value::Integer(n) => feed(&n.to_le_bytes()),
+            Value::Number(x) => feed(&x.to_bits().to_le_bytes()),
+            Value::String(text) => feed(text.as_bytes()),
+            Value::Symbol(symbol) => feed(symbol.as_bytes()),
+            Value::Named(name) => feed(name.as_str().as_bytes()),
+            // Marked, so that a gensym hashes apart from a name of the same bytes.
+            Value::Gensym(n) => {
+                feed(b"#");
+                feed(&n.to_le_bytes());
+            },
+            Value::Cons(car, cdr) => {
+                feed(&hash(car).to_le_bytes());
+                value = cdr.deref();
+                continue;
+            },
+            Value::Ratio(n, d) => {
+                feed(&n.to_le_bytes());
+                feed(&d.to_le_bytes());
+            },
+            Value::Char(c) => feed(&(*c as u32).to_le_bytes()),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(n) => {
+                feed(&[(n.sign() == num_bigint::Sign::Minus) as u8]);
+                for digit in n.iter_u64_digits() {
+                    feed(&digit.to_le_bytes());
+                }
+            },
+            value => feed(&(value as *const Value as usize).to_le_bytes()),
+        }
+
+        return state;
+    }
+}
@@ -1,10 +1,134 @@
-use crate::pool::RcValue;
+use crate::{arena::{Bytes, Text, Vector}, pool::{NamedText, RcValue}, table::Table};
+use core::fmt;
+use core::ops::Deref;
+
+#[cfg(feature = "bigint")]
+use alloc::boxed::Box;
 
 #[derive(Debug)]
 pub enum Value<'s> {
     Integer(i64),
     Number(f64),
-    String(&'s str),
+    String(Text<'s>),
     Symbol(&'s str),
+    /// A symbol that owns its name, as one read by `parser::read` does,
+    /// rather than borrowing it for `'s`.
+    Named(NamedText),
+    /// A symbol made by `gensym`, equal only to itself. It has a number
+    /// where others have a name, and is written `#:g` and the number.
+    Gensym(u64),
     Cons(RcValue<'s>, RcValue<'s>),
+    /// A numerator and denominator in lowest terms, the denominator above
+    /// one; see `Number`.
+    Ratio(i64, i64),
+    Char(char),
+    Vector(Vector<'s>),
+    Table(Table<'s>),
+    Bytes(Bytes<'s>),
+    /// An integer outside the range of `i64`; smaller ones are always
+    /// `Integer`s.
+    #[cfg(feature = "bigint")]
+    BigInt(Box<num_bigint::BigInt>),
+}
+
+pub const CONS: usize = 4;
+
+/// Names of the `Value` variants, indexed by `Value::kind`.
+#[cfg(not(feature = "bigint"))]
+pub const KINDS: [&str; 10] = ["integer", "float", "string", "symbol", "cons", "ratio", "char", "vector", "hash-table", "bytes"];
+#[cfg(feature = "bigint")]
+pub const KINDS: [&str; 11] = ["integer", "float", "string", "symbol", "cons", "ratio", "char", "vector", "hash-table", "bytes", "bignum"];
+
+impl<'s> Value<'s> {
+    pub fn kind(&self) -> usize {
+        match self {
+            Value::Integer(_) => 0,
+            Value::Number(_) => 1,
+            Value::String(_) => 2,
+            Value::Symbol(_) | Value::Named(_) | Value::Gensym(_) => 3,
+            Value::Cons(_, _) => CONS,
+            Value::Ratio(_, _) => 5,
+            Value::Char(_) => 6,
+            Value::Vector(_) => 7,
+            Value::Table(_) => 8,
+            Value::Bytes(_) => 9,
+            #[cfg(feature = "bigint")]
+            Value::BigInt(_) => 10,
+        }
+    }
+
+    /// The name of a symbol, whether borrowed or owned. A gensym has none.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Value::Symbol(name) => Some(name),
+            Value::Named(name) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Whether two values are alike: numbers of the same kind and value, strings
+/// and symbols of the same text, the same character, and conses with equal
+/// cars and cdrs. A vector, hash table or byte buffer is only equal to
+/// itself.
+pub fn equal<'s>(a: &Value<'s>, b: &Value<'s>) -> bool {
+    let (mut a, mut b) = (a, b);
+    loop {
+        return match (a, b) {
+            (Value::Cons(a_car, a_cdr), Value::Cons(b_car, b_cdr)) => {
+                if !equal(a_car, b_car) {
+                    return false;
+                }
+                (a, b) = (a_cdr.deref(), b_cdr.deref());
+                continue;
+            },
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a.to_bits() == b.to_bits(),
+            (Value::String(a), Value::String(b)) => a.as_str() == b.as_str(),
+            (Value::Symbol(_) | Value::Named(_), Value::Symbol(_) | Value::Named(_)) => a.name() == b.name(),
+            (Value::Gensym(a), Value::Gensym(b)) => a == b,
+            (Value::Ratio(a_n, a_d), Value::Ratio(b_n, b_d)) => (a_n, a_d) == (b_n, b_d),
+            (Value::Char(a), Value::Char(b)) => a == b,
+            #[cfg(feature = "bigint")]
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (a, b) => core::ptr::eq(a, b),
+        };
+    }
+}
+
+/// Names of the characters that are written by name, as in `#\space`.
+pub const CHAR_NAMES: [(&str, char); 3] = [("space", ' '), ("newline", '\n'), ("tab", '\t')];
+
+/// Displays a character the way the reader reads it: `#\a`, `#\space`, or
+/// for other characters that do not print, `#\x7f`.
+pub struct CharLiteral(pub char);
+
+impl fmt::Display for CharLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((name, _)) = CHAR_NAMES.iter().find(|(_, c)| *c == self.0) {
+            return write!(f, "#\\{}", name);
+        }
+
+        if self.0.is_control() || self.0.is_whitespace() {
+            write!(f, "#\\x{:x}", self.0 as u32)
+        } else {
+            write!(f, "#\\{}", self.0)
+        }
+    }
+}
+
+/// Displays a byte buffer the way the reader reads it, as in `#u8(01 ff)`.
+pub struct BytesLiteral<'a, 's>(pub &'a Bytes<'s>);
+
+impl fmt::Display for BytesLiteral<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#u8(")?;
+        for index in 0..self.0.len() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02x}", self.0.get(index).unwrap())?;
+        }
+        write!(f, ")")
+    }
 }
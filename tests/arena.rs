@@ -0,0 +1,169 @@
+use myser::{arena::Arena, eval::Cells, pool::{Heap, Pool}, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, run, run_alone};
+
+#[test]
+fn blocks_are_zeroed_and_returned() {
+    let arena: Arena<256> = Arena::new();
+
+    let mut block = arena.alloc(10).unwrap();
+    assert_eq!(block.bytes(), [0; 10]);
+    block.bytes_mut().copy_from_slice(b"0123456789");
+    assert!(arena.used() >= 10);
+
+    drop(block);
+    assert_eq!(arena.used(), 0);
+    assert_eq!(arena.verify(), Ok(()));
+
+    let block = arena.alloc(10).unwrap();
+    assert_eq!(block.bytes(), [0; 10]);
+}
+
+#[test]
+fn freed_neighbours_merge() {
+    let arena: Arena<256> = Arena::new();
+
+    let blocks: Vec<_> = (0..4).map(|_| arena.alloc(40).unwrap()).collect();
+    assert!(arena.alloc(40).is_none());
+
+    let mut blocks = blocks.into_iter();
+    let (a, b, c, d) = (blocks.next(), blocks.next(), blocks.next(), blocks.next());
+    drop(a);
+    drop(c);
+    assert_eq!(arena.verify(), Ok(()));
+    assert!(arena.alloc(100).is_none());
+
+    drop(b);
+    assert_eq!(arena.verify(), Ok(()));
+    let big = arena.alloc(100).unwrap();
+
+    drop(d);
+    drop(big);
+    assert_eq!(arena.used(), 0);
+    assert_eq!(arena.verify(), Ok(()));
+    assert!(arena.alloc(200).is_some());
+}
+
+#[test]
+fn strings_live_as_long_as_their_values() {
+    let pool: Pool<'_, 16, 256> = Pool::new();
+
+    let string = pool.new_string("hello").unwrap();
+    let list = pool.new_cons(string.clone(), pool.new_symbol("nil"));
+    drop(string);
+    assert!(pool.stats().arena_used > 0);
+
+    match list.deref() {
+        Value::Cons(car, _) => match car.deref() {
+            Value::String(text) => assert_eq!(text.as_str(), "hello"),
+            value => panic!("{:?}", value),
+        },
+        value => panic!("{:?}", value),
+    }
+
+    drop(list);
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn full_arena_refuses_blocks() {
+    let pool: Pool<'_, 16, 64> = Pool::new();
+
+    let strings: Vec<_> = (0..4).map_while(|_| pool.alloc_block(20)).collect();
+    assert!(strings.len() < 4);
+    assert!(pool.alloc_block(20).is_none());
+    assert_eq!(pool.stats().arena_capacity, 64);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn growable_pool_takes_blocks_from_the_allocator() {
+    use myser::pool::GrowablePool;
+
+    let pool = GrowablePool::new();
+
+    let strings: Vec<_> = (0..100).map(|n| pool.new_string(&"x".repeat(n)).unwrap()).collect();
+    assert!(pool.stats().arena_used > 0);
+
+    drop(strings);
+    assert_eq!(pool.stats().arena_used, 0);
+}
+
+#[test]
+fn read_names_outlive_their_source_and_die_with_their_symbols() {
+    let pool: Pool<'_, 16, 256> = Pool::new();
+
+    let source = String::from("(a . b)");
+    let (_, datum) = myser::parser::read(&pool, &source).unwrap();
+    drop(source);
+
+    match datum.deref() {
+        Value::Cons(car, cdr) => {
+            assert_eq!(car.name(), Some("a"));
+            assert_eq!(cdr.name(), Some("b"));
+        },
+        value => panic!("{:?}", value),
+    }
+    assert_eq!(pool.stats().live, 1);
+    assert!(pool.stats().arena_used > 0);
+
+    drop(datum);
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn read_from_string_gives_its_names_back() {
+    use myser::{builtins::Builtins, eval::{eval, Cells}, parser::parse};
+
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells: Cells<'_, 16> = Cells::new();
+    let builtins: Builtins<'_, (), 64> = Builtins::new();
+
+    // A hundred reads would fill the arena many times over if their names
+    // were kept.
+    let form = parse(&pool, "(read-from-string \"(a-rather-long-name another-rather-long-name)\")").unwrap().1;
+    let used = pool.stats().arena_used;
+    for _ in 0..100 {
+        eval(&mut (), &pool, &mut cells, &builtins, form.clone()).unwrap();
+    }
+    assert_eq!(pool.stats().arena_used, used);
+
+    // A binding holds on to the name it was made with, and an exit to the
+    // name it is looking for, after the form that named them is gone.
+    let form = parse(&pool, "(progn (eval (read-from-string \"(set name-from-a-string 42)\")) name-from-a-string)").unwrap().1;
+    assert!(matches!(eval(&mut (), &pool, &mut cells, &builtins, form).unwrap().deref(), Value::Integer(42)));
+    let form = parse(&pool, "(block b (eval (read-from-string \"(return-from b 7)\")) 0)").unwrap().1;
+    assert!(matches!(eval(&mut (), &pool, &mut cells, &builtins, form).unwrap().deref(), Value::Integer(7)));
+}
+
+#[test]
+fn literals_with_no_room_fail_to_parse() {
+    use myser::parser::parse;
+
+    let pool: Pool<'_, 16> = Pool::new();
+    for source in ["\"hello\"", "#(1 2)", "#u8(01 02)", "(a \"b\")"] {
+        assert!(matches!(parse(&pool, source), Err(nom::Err::Failure(_))), "{}", source);
+    }
+    assert_eq!(pool.stats().live, 0);
+
+    let pool: Pool<'_, 16, 64> = Pool::new();
+    assert!(parse(&pool, "\"hello\"").is_ok());
+    assert!(matches!(parse(&pool, &format!("\"{}\"", "x".repeat(100))), Err(nom::Err::Failure(_))));
+}
+
+#[test]
+fn strings_with_no_room_are_storage_conditions() {
+    let pool: Pool<'_, 64, 64> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set s (string 'a-name-of-some-thirty-bytes))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (concat s s s) (storage-condition () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (string 'another-name-of-thirty-bytes) (storage-condition () 2))")), 2);
+
+    let pool: Pool<'_, 64> = Pool::new();
+    assert_eq!(integer(&run_alone(&pool, "(handler-case (string 1) (storage-condition () 3))")), 3);
+}
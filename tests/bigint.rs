@@ -0,0 +1,78 @@
+#![cfg(feature = "bigint")]
+
+use myser::{pool::GrowablePool, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{describe, run_alone};
+
+#[test]
+fn overflow_promotes() {
+    let pool = GrowablePool::new();
+
+    assert_eq!(describe(&run_alone(&pool, "(+ 9223372036854775807 1)")), "bignum 9223372036854775808");
+    assert_eq!(describe(&run_alone(&pool, "(- -9223372036854775808 1)")), "bignum -9223372036854775809");
+    assert_eq!(describe(&run_alone(&pool, "(* 4294967296 4294967296)")), "bignum 18446744073709551616");
+    assert_eq!(describe(&run_alone(&pool, "(/ -9223372036854775808 -1)")), "bignum 9223372036854775808");
+}
+
+#[test]
+fn results_that_fit_demote() {
+    let pool = GrowablePool::new();
+
+    assert_eq!(describe(&run_alone(&pool, "(- 9223372036854775808 1)")), "integer 9223372036854775807");
+    assert_eq!(describe(&run_alone(&pool, "(/ 18446744073709551616 4294967296)")), "integer 4294967296");
+    assert_eq!(describe(&run_alone(&pool, "-9223372036854775808")), "integer -9223372036854775808");
+}
+
+#[test]
+fn long_literals_are_read_exactly() {
+    let pool = GrowablePool::new();
+
+    let value = run_alone(&pool, "123456789012345678901234567890");
+    assert_eq!(describe(&value), "bignum 123456789012345678901234567890");
+
+    let value = run_alone(&pool, "(string -123456789012345678901234567890)");
+    match value.deref() {
+        Value::String(text) => assert_eq!(text.as_str(), "-123456789012345678901234567890"),
+        value => panic!("{:?}", value),
+    }
+}
+
+#[test]
+fn bignums_mix_with_floats() {
+    let pool = GrowablePool::new();
+
+    let value = run_alone(&pool, "(+ 18446744073709551616 0.5)");
+    match value.deref() {
+        Value::Number(x) => assert_eq!(*x, 18446744073709551616.5),
+        value => panic!("{:?}", value),
+    }
+}
+
+#[test]
+fn bignum_quotients_are_exact() {
+    let pool = GrowablePool::new();
+
+    let value = run_alone(&pool, "(/ 18446744073709551616 36893488147419103232)");
+    assert!(matches!(value.deref(), Value::Ratio(1, 2)), "{:?}", value);
+
+    let value = run_alone(&pool, "(= 36893488147419103232/2 18446744073709551616)");
+    assert!(matches!(value.deref(), Value::Symbol("t")), "{:?}", value);
+}
+
+#[test]
+fn ash_past_64_bits_promotes() {
+    let pool = GrowablePool::new();
+
+    assert_eq!(describe(&run_alone(&pool, "(ash 1 64)")), "bignum 18446744073709551616");
+    assert_eq!(describe(&run_alone(&pool, "(ash -3 63)")), "bignum -27670116110564327424");
+}
+
+#[test]
+fn radix_literals_grow_too() {
+    let pool = GrowablePool::new();
+
+    assert_eq!(describe(&run_alone(&pool, "#x1_0000_0000_0000_0000")), "bignum 18446744073709551616");
+    assert_eq!(describe(&run_alone(&pool, "-0b1000000000000000000000000000000000000000000000000000000000000000")), "integer -9223372036854775808");
+}
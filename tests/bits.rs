@@ -0,0 +1,72 @@
+use myser::pool::Pool;
+
+mod common;
+use common::{integer, run_alone, truth};
+
+#[test]
+fn logical_operations() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(integer(&run_alone(&pool, "(logand 12 10)")), 8);
+    assert_eq!(integer(&run_alone(&pool, "(logand)")), -1);
+    assert_eq!(integer(&run_alone(&pool, "(logior 12 10 1)")), 15);
+    assert_eq!(integer(&run_alone(&pool, "(logxor 12 10)")), 6);
+    assert_eq!(integer(&run_alone(&pool, "(lognot 0)")), -1);
+    assert_eq!(integer(&run_alone(&pool, "(lognot -6)")), 5);
+}
+
+#[test]
+fn ash_keeps_the_sign() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(integer(&run_alone(&pool, "(ash 1 10)")), 1024);
+    assert_eq!(integer(&run_alone(&pool, "(ash 1024 -3)")), 128);
+    assert_eq!(integer(&run_alone(&pool, "(ash -5 -1)")), -3);
+    assert_eq!(integer(&run_alone(&pool, "(ash -1 -100)")), -1);
+    assert_eq!(integer(&run_alone(&pool, "(ash 5 -64)")), 0);
+    assert_eq!(integer(&run_alone(&pool, "(ash 0 1000)")), 0);
+    assert_eq!(integer(&run_alone(&pool, "(ash -1 63)")), i64::MIN);
+}
+
+#[cfg(not(feature = "bigint"))]
+#[test]
+fn ash_past_64_bits_overflows() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(common::unhandled(&pool, &mut myser::eval::Cells::new(), "(ash 1 63)"), "arithmetic-error");
+}
+
+#[test]
+fn lsh_acts_on_64_bits() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(integer(&run_alone(&pool, "(lsh 1 63)")), i64::MIN);
+    assert_eq!(integer(&run_alone(&pool, "(lsh 3 63)")), i64::MIN);
+    assert_eq!(integer(&run_alone(&pool, "(lsh -1 -60)")), 15);
+    assert_eq!(integer(&run_alone(&pool, "(lsh -1 64)")), 0);
+    assert_eq!(integer(&run_alone(&pool, "(lsh -1 -64)")), 0);
+}
+
+#[test]
+fn bit_tests_and_counts() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert!(truth(&run_alone(&pool, "(logbitp 2 4)")));
+    assert!(!truth(&run_alone(&pool, "(logbitp 1 4)")));
+    assert!(truth(&run_alone(&pool, "(logbitp 200 -1)")));
+    assert!(!truth(&run_alone(&pool, "(logbitp 200 1)")));
+    assert_eq!(integer(&run_alone(&pool, "(logcount 255)")), 8);
+    assert_eq!(integer(&run_alone(&pool, "(logcount -256)")), 8);
+}
+
+#[test]
+fn bit_fields() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(integer(&run_alone(&pool, "(bit-extract 43981 4 8)")), 0xbc);
+    assert_eq!(integer(&run_alone(&pool, "(bit-extract -1 60 8)")), 0xff);
+    assert_eq!(integer(&run_alone(&pool, "(bit-extract -2 0 64)")), -2);
+    assert_eq!(integer(&run_alone(&pool, "(bit-insert 43981 4 8 255)")), 0xaffd);
+    assert_eq!(integer(&run_alone(&pool, "(bit-insert 0 60 8 255)")), -(1 << 60));
+    assert_eq!(integer(&run_alone(&pool, "(bit-insert 0 64 8 255)")), 0);
+}
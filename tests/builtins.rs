@@ -0,0 +1,18 @@
+use myser::builtins::Builtins;
+
+#[test]
+fn the_core_leaves_room_for_a_group() {
+    let builtins: Builtins<'_, (), 64> = Builtins::new();
+    assert!(builtins.get("car").is_some());
+    assert!(builtins.get("sqrt").is_none());
+
+    let builtins: Builtins<'_, (), 64> = Builtins::new().with_math();
+    assert!(builtins.get("sqrt").is_some());
+    assert!(builtins.get("logand").is_none());
+}
+
+#[test]
+#[should_panic(expected = "no room for builtin")]
+fn too_small_a_table_panics() {
+    let _: Builtins<'_, (), 64> = Builtins::new().with_math().with_bits();
+}
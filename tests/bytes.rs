@@ -0,0 +1,126 @@
+use myser::{eval::Cells, parser::parse, pool::{Heap, Pool}, value::{BytesLiteral, Value}};
+use core::ops::Deref;
+
+mod common;
+use common::{bytes, integer, run, run_alone, unhandled};
+
+#[test]
+fn literals_and_constructors() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(bytes(&run(&pool, &mut cells, "#u8(01 ff 7F a)")), [1, 255, 127, 10]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "#u8()")), []);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(make-bytes 3)")), [0, 0, 0]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(make-bytes 2 170)")), [170, 170]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(bytes 1 (+ 1 1))")), [1, 2]);
+    assert!(parse(&pool, "#u8(100)").is_err());
+    assert!(parse(&pool, "#u8(+f)").is_err());
+    assert!(parse(&pool, "#u8(01").is_err());
+}
+
+#[test]
+fn printed_as_read() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let buffer = run(&pool, &mut cells, "#u8(00 0a ff)");
+    let Value::Bytes(contents) = buffer.deref() else { panic!("{:?}", buffer) };
+    assert_eq!(BytesLiteral(contents).to_string(), "#u8(00 0a ff)");
+}
+
+#[test]
+fn indexing_slicing_and_concatenating() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set b #u8(10 20 30 40))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(byte-ref b 1)")), 0x20);
+    assert!(matches!(run(&pool, &mut cells, "(byte-ref b 4)").deref(), Value::Symbol("nil")));
+    assert_eq!(integer(&run(&pool, &mut cells, "(byte-set b 0 255)")), 255);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-length b)")), 4);
+
+    assert_eq!(bytes(&run(&pool, &mut cells, "(subbytes b 1 3)")), [0x20, 0x30]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(subbytes b 2)")), [0x30, 0x40]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(subbytes b 4)")), []);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(bytes-concat b #u8() #u8(01))")), [255, 0x20, 0x30, 0x40, 1]);
+    assert_eq!(bytes(&run(&pool, &mut cells, "(bytes-concat)")), []);
+}
+
+#[test]
+fn reading_fields() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set b #u8(01 02 03 80 00 00 80 3f))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 3 'u8)")), 0x80);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 0 'u16le)")), 0x0201);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 0 'u16be)")), 0x0102);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 0 'u32le)")), 0x80030201);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 0 'u32be)")), 0x01020380);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 0 'i32le)")), -0x7ffcfdff);
+    assert_eq!(integer(&run(&pool, &mut cells, "(bytes-read b 3 'i32be)")), -0x7fffff80);
+    assert!(matches!(run(&pool, &mut cells, "(bytes-read b 4 'f32le)").deref(), Value::Number(x) if *x == 1.0));
+    assert!(matches!(run(&pool, &mut cells, "(bytes-read b 5 'u32le)").deref(), Value::Symbol("nil")));
+    assert!(matches!(run(&pool, &mut cells, "(bytes-read b -1 'u8)").deref(), Value::Symbol("nil")));
+}
+
+#[test]
+fn writing_fields() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set b (make-bytes 8))");
+    run(&pool, &mut cells, "(bytes-write b 0 'u16be 258)");
+    run(&pool, &mut cells, "(bytes-write b 2 'u16le 258)");
+    run(&pool, &mut cells, "(bytes-write b 4 'i32le -2)");
+    assert_eq!(bytes(&run(&pool, &mut cells, "b")), [1, 2, 2, 1, 0xfe, 0xff, 0xff, 0xff]);
+
+    run(&pool, &mut cells, "(bytes-write b 4 'f32be -2)");
+    run(&pool, &mut cells, "(bytes-write b 0 'u32le 4294967295)");
+    assert_eq!(bytes(&run(&pool, &mut cells, "b")), [0xff, 0xff, 0xff, 0xff, 0xc0, 0, 0, 0]);
+}
+
+#[test]
+fn writing_a_value_too_wide_for_the_field_is_an_error() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(bytes-write (make-bytes 4) 0 'u16le 65536)"), "type-error: out of range");
+}
+
+#[test]
+fn writing_past_the_end_is_an_error() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(bytes-write (make-bytes 4) 2 'u32le 0)"), "type-error: out of range");
+}
+
+#[test]
+fn buffers_with_no_room_are_storage_conditions() {
+    let pool: Pool<'_, 64, 64> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (make-bytes 1000000) (storage-condition () 1))")), 1);
+    run(&pool, &mut cells, "(set b (make-bytes 40))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (bytes-concat b b b) (storage-condition () 2))")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (subbytes b 0) (storage-condition () 3))")), 3);
+
+    let pool: Pool<'_, 64> = Pool::new();
+    assert_eq!(integer(&run_alone(&pool, "(handler-case (bytes 1 2 3) (storage-condition () 4))")), 4);
+}
+
+#[test]
+fn released_with_the_buffer() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+
+    let buffer = pool.new_bytes(100, &mut |index| index as u8).unwrap();
+    assert_eq!(pool.stats().live, 1);
+    assert!(pool.stats().arena_used >= 100);
+
+    drop(buffer);
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
@@ -0,0 +1,80 @@
+use myser::{parser::parse, pool::{Heap, Pool}, value::{CharLiteral, Value}};
+use core::ops::Deref;
+
+mod common;
+use common::{char, run_alone};
+
+#[test]
+fn reader_syntax() {
+    let pool: Pool<'_, 64, 256> = Pool::new();
+
+    assert_eq!(char(&run_alone(&pool, "#\\a")), 'a');
+    assert_eq!(char(&run_alone(&pool, "#\\space")), ' ');
+    assert_eq!(char(&run_alone(&pool, "#\\Newline")), '\n');
+    assert_eq!(char(&run_alone(&pool, "#\\tab")), '\t');
+    assert_eq!(char(&run_alone(&pool, "#\\x41")), 'A');
+    assert_eq!(char(&run_alone(&pool, "#\\x")), 'x');
+    assert_eq!(char(&run_alone(&pool, "#\\(")), '(');
+    assert_eq!(char(&run_alone(&pool, "#\\λ")), 'λ');
+    assert!(parse(&pool, "#\\bogus").is_err());
+    assert!(parse(&pool, "#\\xd800").is_err());
+}
+
+#[test]
+fn characters_in_lists() {
+    let pool: Pool<'_, 64, 256> = Pool::new();
+
+    let list = run_alone(&pool, "'(#\\) #\\b)");
+    match list.deref() {
+        Value::Cons(a, rest) => {
+            assert_eq!(char(a), ')');
+            let Value::Cons(b, _) = rest.deref() else { panic!("{:?}", rest) };
+            assert_eq!(char(b), 'b');
+        },
+        value => panic!("{:?}", value),
+    }
+}
+
+#[test]
+fn printed_as_read() {
+    for (c, printed) in [('a', "#\\a"), (' ', "#\\space"), ('\n', "#\\newline"), ('\u{7f}', "#\\x7f"), ('(', "#\\(")] {
+        assert_eq!(CharLiteral(c).to_string(), printed);
+
+        let pool: Pool<'_, 64, 256> = Pool::new();
+        assert_eq!(char(&run_alone(&pool, printed)), c);
+    }
+}
+
+#[test]
+fn builtins() {
+    let pool: Pool<'_, 64, 256> = Pool::new();
+
+    assert!(matches!(run_alone(&pool, "(char->integer #\\A)").deref(), Value::Integer(65)));
+    assert_eq!(char(&run_alone(&pool, "(integer->char 955)")), 'λ');
+    assert!(matches!(run_alone(&pool, "(integer->char -1)").deref(), Value::Symbol("nil")));
+    assert_eq!(char(&run_alone(&pool, "(char-upcase #\\a)")), 'A');
+    assert_eq!(char(&run_alone(&pool, "(char-upcase #\\ß)")), 'ß');
+    assert!(matches!(run_alone(&pool, "(char-alphabetic-p #\\é)").deref(), Value::Symbol("t")));
+    assert!(matches!(run_alone(&pool, "(char-alphabetic-p #\\1)").deref(), Value::Symbol("nil")));
+    assert_eq!(char(&run_alone(&pool, "(string-ref \"héllo\" 1)")), 'é');
+    assert!(matches!(run_alone(&pool, "(string-ref \"abc\" 3)").deref(), Value::Symbol("nil")));
+
+    let string = run_alone(&pool, "(string #\\z)");
+    match string.deref() {
+        Value::String(text) => assert_eq!(text.as_str(), "z"),
+        value => panic!("{:?}", value),
+    }
+}
+
+#[test]
+fn ascii_characters_take_no_cells() {
+    let pool: Pool<'_, 64, 256> = Pool::new();
+
+    let chars = [run_alone(&pool, "#\\a"), run_alone(&pool, "(integer->char 65)"), run_alone(&pool, "(char-upcase #\\b)"), run_alone(&pool, "(string-ref \"xyz\" 2)")];
+    assert_eq!(chars.iter().map(char).collect::<String>(), "aABz");
+    assert_eq!(pool.stats().live, 0);
+
+    let lambda = run_alone(&pool, "#\\λ");
+    assert_eq!(char(&lambda), 'λ');
+    assert_eq!(pool.stats().live, 1);
+}
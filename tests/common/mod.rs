@@ -0,0 +1,126 @@
+//! Helpers shared by the integration tests, each of which uses only some.
+#![allow(dead_code)]
+
+use myser::{builtins::Builtins, eval::{eval, Cells, Unwind}, parser::parse, pool::{Heap, RcValue}, value::{Value, KINDS}};
+use core::ops::Deref;
+
+/// Reads the one form `source` holds, all of it.
+pub fn read<'s>(pool: &'s dyn Heap<'s>, source: &'s str) -> RcValue<'s> {
+    let (rest, datum) = parse(pool, source).unwrap();
+    assert_eq!(rest, "", "{:?} was not read whole", source);
+
+    datum
+}
+
+/// Evaluates `source` with every builtin group and the bindings in `cells`,
+/// returning its value or the exit nothing in it took.
+pub fn try_run<'s>(pool: &'s dyn Heap<'s>, cells: &mut Cells<'s, 16>, source: &'s str) -> Result<RcValue<'s>, Unwind<'s>> {
+    let builtins: Builtins<'_, (), 128> = Builtins::new()
+        .with_math()
+        .with_types()
+        .with_bits()
+        .with_vectors()
+        .with_tables()
+        .with_bytes();
+    let form = parse(pool, source).unwrap().1;
+
+    eval(&mut (), pool, cells, &builtins, form)
+}
+
+/// The value of `source`, as `try_run` gives it, which must not exit.
+pub fn run<'s>(pool: &'s dyn Heap<'s>, cells: &mut Cells<'s, 16>, source: &'s str) -> RcValue<'s> {
+    try_run(pool, cells, source).unwrap_or_else(|unwind| panic!("{}", unwind))
+}
+
+/// The exit `source` ends with, as it is displayed, as in
+/// `"type-error: not a list"`.
+pub fn unhandled<'s>(pool: &'s dyn Heap<'s>, cells: &mut Cells<'s, 16>, source: &'s str) -> String {
+    match try_run(pool, cells, source) {
+        Ok(value) => panic!("{:?} did not exit", value),
+        Err(unwind) => unwind.to_string(),
+    }
+}
+
+/// Evaluates `source` like `run`, without the bindings of any other run.
+pub fn run_alone<'s>(pool: &'s dyn Heap<'s>, source: &'s str) -> RcValue<'s> {
+    run(pool, &mut Cells::new(), source)
+}
+
+pub fn integer(value: &RcValue) -> i64 {
+    match value.deref() {
+        Value::Integer(n) => *n,
+        value => panic!("{:?}", value),
+    }
+}
+
+/// The elements of a proper list or a vector, each of which must be an
+/// integer.
+pub fn integers(value: &RcValue) -> Vec<i64> {
+    if let Value::Vector(vector) = value.deref() {
+        return (0..vector.len()).map(|index| integer(&vector.get(index).unwrap())).collect();
+    }
+
+    let mut list = value.deref();
+    let mut integers = Vec::new();
+    while let Value::Cons(car, cdr) = list {
+        integers.push(integer(car));
+        list = cdr;
+    }
+    assert!(matches!(list, Value::Symbol("nil")), "{:?}", value);
+
+    integers
+}
+
+/// The bytes of a byte buffer.
+pub fn bytes(value: &RcValue) -> Vec<u8> {
+    let Value::Bytes(buffer) = value.deref() else { panic!("{:?}", value) };
+    (0..buffer.len()).map(|index| buffer.get(index).unwrap()).collect()
+}
+
+pub fn float(value: &RcValue) -> f64 {
+    match value.deref() {
+        Value::Number(x) => *x,
+        value => panic!("{:?}", value),
+    }
+}
+
+pub fn char(value: &RcValue) -> char {
+    match value.deref() {
+        Value::Char(c) => *c,
+        value => panic!("{:?}", value),
+    }
+}
+
+pub fn symbol<'a>(value: &'a RcValue) -> &'a str {
+    match value.name() {
+        Some(name) => name,
+        None => panic!("{:?}", value),
+    }
+}
+
+/// Whether `value` is `t` rather than nil.
+pub fn truth(value: &RcValue) -> bool {
+    match value.deref() {
+        Value::Symbol("t") => true,
+        Value::Symbol("nil") => false,
+        value => panic!("{:?}", value),
+    }
+}
+
+/// A number as it is written, or the name of a symbol.
+pub fn show(value: &RcValue) -> String {
+    match value.deref() {
+        Value::Integer(n) => format!("{}", n),
+        #[cfg(feature = "bigint")]
+        Value::BigInt(n) => format!("{}", n),
+        Value::Ratio(n, d) => format!("{}/{}", n, d),
+        Value::Number(x) => format!("{:?}", x),
+        Value::Symbol(_) | Value::Named(_) => symbol(value).to_string(),
+        value => panic!("{:?}", value),
+    }
+}
+
+/// `show` after the kind of the value, as in `"ratio 1/2"`.
+pub fn describe(value: &RcValue) -> String {
+    format!("{} {}", KINDS[value.kind()], show(value))
+}
@@ -0,0 +1,155 @@
+use myser::{eval::Cells, pool::Pool};
+
+mod common;
+use common::{integer, run, symbol, unhandled};
+
+#[test]
+fn catch_and_throw() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(catch 'done (set a 1) (throw 'done 2) (set a 3))")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "a")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(catch 'outer (+ 1 (catch 'inner (throw 'outer 10))))")), 10);
+    assert_eq!(integer(&run(&pool, &mut cells, "(catch 'outer (+ 1 (catch 'inner (throw 'inner 10))))")), 11);
+    assert_eq!(symbol(&run(&pool, &mut cells, "(catch '(a 1) (throw (list 'a 1)))")), "nil");
+    assert_eq!(integer(&run(&pool, &mut cells, "(catch 'done 5)")), 5);
+}
+
+#[test]
+fn throws_leave_loops() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (set i 0)
+        (catch 'found
+          (while 1
+            (set i (+ i 1))
+            (throw 'found (* i 10))
+            (set i 100))))");
+    assert_eq!(integer(&result), 10);
+    assert_eq!(integer(&run(&pool, &mut cells, "i")), 1);
+}
+
+#[test]
+fn handler_case_picks_a_clause_by_type() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(handler-case (error 'bad-frame \"short\" 3) (other (c) 1) (bad-frame (c) c))");
+    assert_eq!(format!("{:?}", result), "Cons(Symbol(\"bad-frame\"), Cons(String(\"short\"), Cons(Integer(3), Symbol(\"nil\"))))");
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (error \"oops\") (error () 2))")), 2);
+    assert_eq!(symbol(&run(&pool, &mut cells, "(handler-case (error \"oops\") (error (c) (car c)))")), "simple-error");
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (+ 1 2) (error () 0))")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (handler-case (error 'outer \"x\") (inner () 1)) (outer () 2))")), 2);
+    assert_eq!(symbol(&run(&pool, &mut cells, "c")), "nil");
+}
+
+#[test]
+fn ignore_errors() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(symbol(&run(&pool, &mut cells, "(ignore-errors (set b 1) (error \"oops\") (set b 2) 3)")), "nil");
+    assert_eq!(integer(&run(&pool, &mut cells, "b")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(ignore-errors 1 2)")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "(catch 'out (ignore-errors (throw 'out 4)))")), 4);
+}
+
+#[test]
+fn builtin_failures_are_errors() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(symbol(&run(&pool, &mut cells, "(ignore-errors (/ 1 0))")), "nil");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(ignore-errors (car 5))")), "nil");
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (/ 1 0) (type-error () 1) (division-by-zero () 2))")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (car 5) (type-error () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (+ 1 'a) (type-error () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (vector-ref (vector 1)) (program-error () 3))")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (vector-set! (vector 1) 5 0) (error () 4))")), 4);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (read-from-string \"(a b\") (parse-error () 5))")), 5);
+
+    let result = run(&pool, &mut cells, "(handler-case (car 5) (type-error (c) c))");
+    assert_eq!(format!("{:?}", result), "Cons(Symbol(\"type-error\"), Cons(String(\"not a list\"), Symbol(\"nil\")))");
+
+    run(&pool, &mut cells, "(set x 1)");
+    run(&pool, &mut cells, "(ignore-errors (set x (+ x 1)) (car x) (set x 10))");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 2);
+}
+
+#[test]
+fn an_unhandled_builtin_failure_is_returned() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(car 5)"), "type-error: not a list");
+    run(&pool, &mut cells, "(set h (make-hash-table 2))");
+    run(&pool, &mut cells, "(puthash 1 2 h)");
+    assert_eq!(unhandled(&pool, &mut cells, "(maphash 'remhash h)"), "type-error: not a hash table");
+}
+
+#[test]
+fn unwind_protect_always_cleans_up() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(unwind-protect 1 (set cleaned 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "cleaned")), 1);
+
+    run(&pool, &mut cells, "(catch 'out (unwind-protect (throw 'out 0) (set cleaned 2)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "cleaned")), 2);
+
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (unwind-protect (error \"oops\") (set cleaned 3)) (error () cleaned))")), 3);
+
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(catch 'second (catch 'first (unwind-protect (throw 'first 1) (throw 'second 2))))")), 2);
+}
+
+#[test]
+fn bindings_are_restored_on_unwinding() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set x 1)");
+    run(&pool, &mut cells, "(ignore-errors (let- (x . 2) (let- (y . 3) (error \"oops\"))))");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+    assert_eq!(symbol(&run(&pool, &mut cells, "y")), "nil");
+
+    run(&pool, &mut cells, "(catch 'out (let- (x . 4) (throw 'out x)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+
+    run(&pool, &mut cells, "(defmacro fail (x) (error \"in expansion\"))");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(ignore-errors (fail 5))")), "nil");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+}
+
+#[test]
+fn builtins_are_not_called_after_an_exit() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set v (make-vector 1 0))");
+    run(&pool, &mut cells, "(catch 'out (vector-set! v 0 (throw 'out 2)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(vector-ref v 0)")), 0);
+}
+
+#[test]
+fn an_unhandled_error_is_returned() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(progn (error \"unhandled\") 1)"), "simple-error: unhandled");
+}
+
+#[test]
+fn an_uncaught_throw_is_returned() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert!(unhandled(&pool, &mut cells, "(catch 'a (throw 'b 1))").starts_with("no catch for"));
+}
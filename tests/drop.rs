@@ -0,0 +1,64 @@
+use myser::{pool::{Heap, Pool, RcValue}, value::Value};
+use core::ops::Deref;
+
+const CELLS: usize = 200_000;
+
+/// Puts the pool on the heap; it is far too large for a test thread's stack.
+fn pool<'s>() -> Box<Pool<'s, CELLS>> {
+    // An all-zero pool is an empty pool.
+    unsafe { Box::new_zeroed().assume_init() }
+}
+
+#[test]
+fn long_list() {
+    let pool = pool();
+    let pool = &*pool;
+
+    let mut list = pool.new_symbol("nil");
+    for n in 0..CELLS as i64 / 2 {
+        list = pool.new_cons(pool.new_number(n as f64), list);
+    }
+    assert_eq!(pool.stats().live, CELLS);
+
+    drop(list);
+    assert_eq!(pool.stats().live, 0);
+}
+
+#[test]
+fn deep_tree() {
+    let pool = pool();
+    let pool = &*pool;
+
+    let mut tree = pool.new_symbol("nil");
+    for n in 0..CELLS as i64 / 2 {
+        tree = pool.new_cons(tree, pool.new_number(n as f64));
+    }
+    assert_eq!(pool.stats().live, CELLS);
+
+    drop(tree);
+    assert_eq!(pool.stats().live, 0);
+}
+
+#[test]
+fn shared_tails_survive() {
+    let pool = pool();
+    let pool = &*pool;
+
+    let mut tail = pool.new_symbol("nil");
+    for n in 0..1000 {
+        tail = pool.new_cons(pool.new_integer(n), tail);
+    }
+    let head: RcValue = pool.new_cons(pool.new_integer(-1), tail.clone());
+    let live = pool.stats().live;
+
+    drop(head);
+    assert_eq!(pool.stats().live, live - 1);
+
+    let mut length = 0;
+    let mut list = &tail;
+    while let Value::Cons(_, cdr) = list.deref() {
+        length += 1;
+        list = cdr;
+    }
+    assert_eq!(length, 1000);
+}
@@ -0,0 +1,128 @@
+use myser::{eval::Cells, pool::Pool, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, run, unhandled};
+
+#[test]
+fn return_from_a_block() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(block b 1 (return-from b 2) 3)")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "(block b 1 2)")), 2);
+    assert!(matches!(run(&pool, &mut cells, "(block b (return-from b) 3)").deref(), Value::Symbol("nil")));
+    assert_eq!(integer(&run(&pool, &mut cells, "(block outer (+ 1 (block inner (return-from outer 10))))")), 10);
+    assert_eq!(integer(&run(&pool, &mut cells, "(block outer (+ 1 (block inner (return-from inner 10))))")), 11);
+}
+
+#[test]
+fn return_from_leaves_loops() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (set i 0)
+        (block search
+          (while (< i 100)
+            (set i (+ i 1))
+            (while (= i 7) (return-from search (* i 10))))))");
+    assert_eq!(integer(&result), 70);
+    assert_eq!(integer(&run(&pool, &mut cells, "i")), 7);
+}
+
+#[test]
+fn break_stops_the_innermost_loop() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(progn
+        (set i 0)
+        (set inner 0)
+        (while (< i 3)
+          (set i (+ i 1))
+          (while 1
+            (set inner (+ inner 1))
+            (break)
+            (set inner 100))))");
+    assert_eq!(integer(&run(&pool, &mut cells, "i")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells, "inner")), 3);
+}
+
+#[test]
+fn continue_skips_the_rest_of_the_body() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(progn
+        (set i 0)
+        (set skipped 0)
+        (while (< i 5)
+          (set i (+ i 1))
+          (continue)
+          (set skipped (+ skipped 1))))");
+    assert_eq!(integer(&run(&pool, &mut cells, "i")), 5);
+    assert_eq!(integer(&run(&pool, &mut cells, "skipped")), 0);
+}
+
+#[test]
+fn break_in_a_condition_stops_only_its_loop() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn (set n 0) (dotimes (j 3) (set n (+ n 1)) (while (break) 1)) n)");
+    assert_eq!(integer(&result), 3);
+}
+
+#[test]
+fn bindings_are_restored_on_exits() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set x 1)");
+    run(&pool, &mut cells, "(while 1 (let- (x . 2) (break)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+
+    run(&pool, &mut cells, "(set n 0)");
+    run(&pool, &mut cells, "(while (< n 2) (set n (+ n 1)) (let- (x . n) (continue)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(block b (let- (x . 3) (return-from b x)))")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells, "x")), 1);
+}
+
+#[test]
+fn exits_run_cleanup_on_the_way() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set cleaned 0)");
+    run(&pool, &mut cells, "(while 1 (unwind-protect (break) (set cleaned (+ cleaned 1))))");
+    run(&pool, &mut cells, "(block b (unwind-protect (return-from b) (set cleaned (+ cleaned 1))))");
+    assert_eq!(integer(&run(&pool, &mut cells, "cleaned")), 2);
+}
+
+#[test]
+fn catch_lets_other_exits_through() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(block b (catch 'tag (return-from b 1)) 2)")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(progn (set i 0) (while 1 (set i (+ i 1)) (ignore-errors (break))) i)")), 1);
+}
+
+#[test]
+fn returning_from_no_block_is_returned() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(block a (return-from b 1))"), "no block named b");
+}
+
+#[test]
+fn breaking_outside_a_loop_is_returned() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(progn (break) 1)"), "break outside a loop");
+}
@@ -0,0 +1,86 @@
+use myser::{parser::parse, pool::Pool, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{describe, read};
+
+#[test]
+fn radix_prefixes() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    assert_eq!(describe(&read(&pool, "#x1F")), "integer 31");
+    assert_eq!(describe(&read(&pool, "0x1f")), "integer 31");
+    assert_eq!(describe(&read(&pool, "-0x10")), "integer -16");
+    assert_eq!(describe(&read(&pool, "#x-10")), "integer -16");
+    assert_eq!(describe(&read(&pool, "#b1010")), "integer 10");
+    assert_eq!(describe(&read(&pool, "0b1010")), "integer 10");
+    assert_eq!(describe(&read(&pool, "#o17")), "integer 15");
+    assert_eq!(describe(&read(&pool, "0o17")), "integer 15");
+    assert_eq!(describe(&read(&pool, "#x7fff_ffff_ffff_ffff")), "integer 9223372036854775807");
+    assert_eq!(describe(&read(&pool, "#x-8000000000000000")), "integer -9223372036854775808");
+    assert!(parse(&pool, "#b102").is_err());
+}
+
+#[test]
+fn digit_separators() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    assert_eq!(describe(&read(&pool, "1_000_000")), "integer 1000000");
+    assert_eq!(describe(&read(&pool, "1_000.000_1")), "float 1000.0001");
+    assert_eq!(describe(&read(&pool, "1_0/2_0")), "ratio 1/2");
+    assert_eq!(describe(&read(&pool, "_1")), "symbol _1");
+    assert_eq!(describe(&read(&pool, "1_")), "symbol 1_");
+    assert_eq!(describe(&read(&pool, "1__0")), "symbol 1__0");
+}
+
+#[test]
+fn float_forms() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    assert_eq!(describe(&read(&pool, "1.")), "float 1.0");
+    assert_eq!(describe(&read(&pool, ".5")), "float 0.5");
+    assert_eq!(describe(&read(&pool, "-.5")), "float -0.5");
+    assert_eq!(describe(&read(&pool, "1e3")), "float 1000.0");
+    assert_eq!(describe(&read(&pool, "2.5E-1")), "float 0.25");
+    assert_eq!(describe(&read(&pool, "+inf.0")), "float inf");
+    assert_eq!(describe(&read(&pool, "-inf.0")), "float -inf");
+    assert_eq!(describe(&read(&pool, "+nan.0")), "float NaN");
+}
+
+#[test]
+fn what_is_not_a_number_is_a_symbol() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    for symbol in ["+", "-", ".5.", "1+", "-x", "inf", "nan", "1e", "0x", "1.5abc", "..."] {
+        assert_eq!(describe(&read(&pool, symbol)), format!("symbol {}", symbol));
+    }
+
+    assert_eq!(describe(&read(&pool, "+5")), "integer 5");
+    assert_eq!(describe(&read(&pool, "-0")), "integer 0");
+}
+
+#[test]
+fn dotted_pairs_still_read() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    let pair = read(&pool, "(1 . .5)");
+    match pair.deref() {
+        Value::Cons(car, cdr) => assert_eq!((describe(car), describe(cdr)), ("integer 1".into(), "float 0.5".into())),
+        value => panic!("{:?}", value),
+    }
+
+    let list = read(&pool, "(1 .5)");
+    match list.deref() {
+        Value::Cons(_, cdr) => assert!(matches!(cdr.deref(), Value::Cons(..))),
+        value => panic!("{:?}", value),
+    }
+}
+
+#[cfg(not(feature = "bigint"))]
+#[test]
+fn integers_past_64_bits_read_as_floats() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    assert_eq!(describe(&read(&pool, "18446744073709551616")), "float 1.8446744073709552e19");
+    assert_eq!(describe(&read(&pool, "#x1_0000_0000_0000_0000")), "float 1.8446744073709552e19");
+}
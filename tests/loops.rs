@@ -0,0 +1,142 @@
+use myser::{builtins::Builtins, eval::{eval, Cells}, parser::parse, pool::{Heap, Pool}, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, integers, run};
+
+#[test]
+fn dotimes() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set total 0)");
+    assert!(matches!(run(&pool, &mut cells, "(dotimes (i 5) (set total (+ total i)))").deref(), Value::Symbol("nil")));
+    assert_eq!(integer(&run(&pool, &mut cells, "total")), 10);
+    assert_eq!(integer(&run(&pool, &mut cells, "(dotimes (i 3 (* i 10)))")), 30);
+    assert_eq!(integer(&run(&pool, &mut cells, "(dotimes (i -2 i))")), 0);
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("nil")));
+}
+
+#[test]
+fn dolist() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set total 0)");
+    run(&pool, &mut cells, "(dolist (x '(1 2 3)) (set total (+ total x)))");
+    assert_eq!(integer(&run(&pool, &mut cells, "total")), 6);
+    assert_eq!(integer(&run(&pool, &mut cells, "(dolist (x nil total))")), 6);
+    assert!(matches!(run(&pool, &mut cells, "(dolist (x '(1) x))").deref(), Value::Symbol("nil")));
+}
+
+#[test]
+fn do_steps_in_parallel() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let fibonacci = run(&pool, &mut cells, "(do ((n 0 (+ n 1)) (a 0 b) (b 1 (+ a b))) ((= n 10) a))");
+    assert_eq!(integer(&fibonacci), 55);
+
+    run(&pool, &mut cells, "(set count 0)");
+    let result = run(&pool, &mut cells, "(do ((i 0 (+ i 1)) (unstepped 7)) ((>= i 3) unstepped) (set count (+ count 1)))");
+    assert_eq!(integer(&result), 7);
+    assert_eq!(integer(&run(&pool, &mut cells, "count")), 3);
+    assert!(matches!(run(&pool, &mut cells, "(do ((i 0 (+ i 1))) ((= i 2)))").deref(), Value::Symbol("nil")));
+}
+
+#[test]
+fn loop_clauses() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for x in '(1 2 3) collect (* x x))")), [1, 4, 9]);
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for i from 1 to 4 collect i)")), [1, 2, 3, 4]);
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for i from 1 below 4 collect i)")), [1, 2, 3]);
+    assert_eq!(integer(&run(&pool, &mut cells, "(loop for i from 1 to 100 sum i)")), 5050);
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for i from 0 until (> i 3) collect i)")), [0, 1, 2, 3]);
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for i from 5 to 1 collect i)")), []);
+    assert!(matches!(run(&pool, &mut cells, "(loop for x in '(1 2) do x)").deref(), Value::Symbol("nil")));
+
+    let sum = run(&pool, &mut cells, "(loop for x in '(1 1/2 0.25) sum x)");
+    assert!(matches!(sum.deref(), Value::Number(x) if *x == 1.75), "{:?}", sum);
+
+    run(&pool, &mut cells, "(set n 0)");
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop do (set n (+ n 1)) until (= n 3) collect n)")), [1, 2]);
+}
+
+#[test]
+fn loops_honour_exits_and_restore_bindings() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set i 'outer)");
+    assert!(matches!(run(&pool, &mut cells, "(dotimes (i 10 i) (break))").deref(), Value::Symbol("nil")));
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("outer")));
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(block b (dolist (i '(1 2 3)) (return-from b i)))")), 1);
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("outer")));
+
+    assert_eq!(integers(&run(&pool, &mut cells, "(loop for i from 1 to 3 collect i do (continue) collect 0)")), [1, 2, 3]);
+    assert!(matches!(run(&pool, &mut cells, "(loop for i from 1 collect i do (break))").deref(), Value::Symbol("nil")));
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("outer")));
+
+    assert!(matches!(run(&pool, &mut cells, "(ignore-errors (do ((i 0 (error \"step\"))) (nil)))").deref(), Value::Symbol("nil")));
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("outer")));
+}
+
+#[test]
+fn malformed_loops_are_errors() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (dotimes (i 'a) i) (type-error () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (loop for i across '(1)) (program-error () 2))")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (loop for i from 'a) (type-error () 3))")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (loop for i from 0 to 'b) (type-error () 4))")), 4);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (loop for i from 0 until) (program-error () 5))")), 5);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (loop repeat 3) (program-error () 6))")), 6);
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (loop for i from 0 to 3 collect i sum i) (program-error () 7))")), 7);
+
+    let specs: String = (0..17).map(|n| format!("(v{} 0)", n)).collect();
+    let source = format!("(handler-case (do ({}) (t 0)) (program-error () 8))", specs);
+    assert_eq!(integer(&run(&pool, &mut cells, Box::leak(source.into_boxed_str()))), 8);
+}
+
+#[test]
+fn counting_past_the_largest_integer_overflows() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set i 'outer)");
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (loop for i from 9223372036854775806 until (< i 0) sum 0) (arithmetic-error () 1))")), 1);
+    assert!(matches!(run(&pool, &mut cells, "i").deref(), Value::Symbol("outer")));
+}
+
+#[test]
+fn counting_conses_nothing() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells: Cells<'_, 16> = Cells::new();
+    let builtins: Builtins<'_, (), 64> = Builtins::new();
+
+    // Counts within the small integers, which take no cells.
+    let form = parse(&pool, "(dotimes (i 200) (dolist (x '(1 2 3)) x))").unwrap().1;
+    let allocations = pool.stats().allocations;
+    eval(&mut (), &pool, &mut cells, &builtins, form).unwrap();
+    assert_eq!(pool.stats().allocations, allocations);
+}
+
+#[test]
+fn collecting_conses_each_value_once() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells: Cells<'_, 16> = Cells::new();
+    let builtins: Builtins<'_, (), 64> = Builtins::new();
+
+    // Small integers take no cells, so the only allocations are the conses.
+    let form = parse(&pool, "(loop for i from 1 to 200 collect i)").unwrap().1;
+    let allocations = pool.stats().allocations;
+    let list = eval(&mut (), &pool, &mut cells, &builtins, form).unwrap();
+    assert_eq!(integers(&list).len(), 200);
+    assert_eq!(pool.stats().allocations, allocations + 200, "{}", pool.stats());
+}
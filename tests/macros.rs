@@ -0,0 +1,104 @@
+use myser::{builtins::Builtins, eval::{eval, Cells}, parser::parse, pool::{Heap, Pool}, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, run};
+
+#[test]
+fn macros_receive_unevaluated_forms() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (defmacro incf (place) (list 'set place (list '+ place 1)))
+        (set x 41)
+        (incf x)
+        x)");
+    assert_eq!(integer(&result), 42);
+}
+
+#[test]
+fn rest_parameters_take_the_remaining_forms() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (defmacro my-progn (&rest body) (cons 'progn body))
+        (macroexpand '(my-progn 1 2 3)))");
+    let Value::Cons(head, _) = result.deref() else { panic!("{:?}", result) };
+    assert!(matches!(head.deref(), Value::Symbol("progn")));
+}
+
+#[test]
+fn macroexpand_1_expands_once() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (defmacro outer () '(inner))
+        (defmacro inner () 7)
+        (list (macroexpand-1 '(outer)) (macroexpand '(outer))))");
+    let Value::Cons(once, rest) = result.deref() else { panic!("{:?}", result) };
+    let Value::Cons(fully, _) = rest.deref() else { panic!("{:?}", result) };
+    assert!(matches!(once.deref(), Value::Cons(..)));
+    assert_eq!(integer(fully), 7);
+}
+
+#[test]
+fn cached_expansions_are_not_expanded_again() {
+    let source = "(progn
+        (set expansions 0)
+        (set i 3)
+        (defmacro noop () (set expansions (+ expansions 1)) 0)
+        (while i (noop) (set i (- i 1)))
+        expansions)";
+
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+    assert_eq!(integer(&run(&pool, &mut cells, source)), 3);
+
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+    cells.cache_expansions();
+    assert_eq!(integer(&run(&pool, &mut cells, source)), 1);
+}
+
+#[test]
+fn gensyms_are_distinct() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(list (gensym) (gensym))");
+    let Value::Cons(a, rest) = result.deref() else { panic!("{:?}", result) };
+    let Value::Cons(b, _) = rest.deref() else { panic!("{:?}", result) };
+    match (a.deref(), b.deref()) {
+        (Value::Gensym(a), Value::Gensym(b)) => assert_ne!(a, b),
+        values => panic!("{:?}", values),
+    }
+}
+
+#[test]
+fn gensyms_take_no_text() {
+    let pool: Pool<'_, 4096, 8192> = Pool::new();
+    let mut cells: Cells<'_, 16> = Cells::new();
+    let builtins: Builtins<'_, (), 64> = Builtins::new();
+
+    let form = parse(&pool, "(gensym)").unwrap().1;
+    for _ in 0..1000 {
+        eval(&mut (), &pool, &mut cells, &builtins, form.clone()).unwrap();
+    }
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.stats().live, 2, "{}", pool.stats());
+}
+
+#[test]
+fn gensyms_name_variables_and_macros() {
+    let pool: Pool<'_, 1024, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(defmacro with-temp (value body) (let- (temp . (gensym)) (list 'let- (cons temp value) (list 'list temp body))))");
+    let result = run(&pool, &mut cells, "(progn (set temp 1) (with-temp 2 temp))");
+    assert_eq!(format!("{:?}", result), "Cons(Integer(2), Cons(Integer(1), Symbol(\"nil\")))");
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(let- (g . (gensym)) (eval (list 'defmacro g '() 7)) (eval (list g)))")), 7);
+}
@@ -0,0 +1,79 @@
+use myser::pool::Pool;
+
+mod common;
+use common::{float, run_alone, show};
+
+#[test]
+fn mod_follows_the_divisor_and_rem_the_dividend() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(mod -7 2)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(rem -7 2)")), "-1");
+    assert_eq!(show(&run_alone(&pool, "(mod 7 -2)")), "-1");
+    assert_eq!(show(&run_alone(&pool, "(rem 7 -2)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(mod 7/2 1)")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "(mod -5.5 2)")), "0.5");
+    assert_eq!(show(&run_alone(&pool, "(rem -5.5 2)")), "-1.5");
+}
+
+#[test]
+fn abs_min_and_max() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(abs -3)")), "3");
+    assert_eq!(show(&run_alone(&pool, "(abs -1/2)")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "(abs -2.5)")), "2.5");
+    assert_eq!(show(&run_alone(&pool, "(min 3 1/2 2.0)")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "(max 3 1/2 2.0)")), "3");
+    assert_eq!(show(&run_alone(&pool, "(max 1 1.0)")), "1");
+}
+
+#[test]
+fn expt_is_exact_for_integer_powers() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(expt 2 10)")), "1024");
+    assert_eq!(show(&run_alone(&pool, "(expt 2 -2)")), "1/4");
+    assert_eq!(show(&run_alone(&pool, "(expt 2/3 3)")), "8/27");
+    assert_eq!(show(&run_alone(&pool, "(expt 7 0)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(expt 4 0.5)")), "2.0");
+    assert_eq!(show(&run_alone(&pool, "(expt 1.5 2)")), "2.25");
+}
+
+#[test]
+fn transcendental_functions_give_floats() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(float(&run_alone(&pool, "(sqrt 16)")), 4.0);
+    assert_eq!(float(&run_alone(&pool, "(exp 0)")), 1.0);
+    assert_eq!(float(&run_alone(&pool, "(log 1)")), 0.0);
+    assert!((float(&run_alone(&pool, "(log 8 2)")) - 3.0).abs() < 1e-12);
+    assert_eq!(float(&run_alone(&pool, "(sin 0)")), 0.0);
+    assert_eq!(float(&run_alone(&pool, "(cos 0)")), 1.0);
+    assert!((float(&run_alone(&pool, "(* 4 (atan 1))")) - core::f64::consts::PI).abs() < 1e-12);
+    assert!((float(&run_alone(&pool, "(atan 1 -1)")) - 3.0 * core::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    assert!(float(&run_alone(&pool, "(sqrt -1)")).is_nan());
+}
+
+#[test]
+fn rounding_gives_integers() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(floor 2.7)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(ceiling 2.1)")), "3");
+    assert_eq!(show(&run_alone(&pool, "(ceiling -7/2)")), "-3");
+    assert_eq!(show(&run_alone(&pool, "(truncate -2.7)")), "-2");
+    assert_eq!(show(&run_alone(&pool, "(truncate -7/2)")), "-3");
+    assert_eq!(show(&run_alone(&pool, "(round 0.5)")), "0");
+    assert_eq!(show(&run_alone(&pool, "(round 1.5)")), "2");
+}
+
+#[test]
+fn float_and_integer_convert() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(float 1/4)")), "0.25");
+    assert_eq!(show(&run_alone(&pool, "(float 3)")), "3.0");
+    assert_eq!(show(&run_alone(&pool, "(integer 2.9)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(integer -2.9)")), "-2");
+}
@@ -0,0 +1,167 @@
+//! Small enough to run under Miri, which passes all of it:
+//! `cargo +nightly miri test --test pool --features debug`.
+
+use myser::{pool::{Heap, Pool, StaticPool}, value::Value};
+use core::ops::Deref;
+
+#[test]
+fn release_returns_cells() {
+    let pool: Pool<'_, 16> = Pool::new();
+
+    let a = pool.new_number(1.0);
+    let b = pool.new_cons(a.clone(), pool.new_number(2.0));
+    assert_eq!(pool.stats().live, 3);
+    assert_eq!(pool.verify(), Ok(()));
+
+    drop(a);
+    assert_eq!(pool.stats().live, 3);
+
+    drop(b);
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.stats().high_water, 3);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn freed_cells_are_reused() {
+    let pool: Pool<'_, 4> = Pool::new();
+
+    let mut values: Vec<_> = (0..4).map(|n| pool.new_number(n as f64)).collect();
+    values.remove(1);
+    values.remove(2);
+    values.push(pool.new_number(4.0));
+    values.push(pool.new_number(5.0));
+
+    let numbers: Vec<f64> = values.iter().map(|value| match value.deref() {
+        Value::Number(x) => *x,
+        value => panic!("{:?}", value),
+    }).collect();
+    assert_eq!(numbers, [0.0, 2.0, 4.0, 5.0]);
+    assert_eq!(pool.stats().free, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn immediates_take_no_cells() {
+    let pool: Pool<'_, 4> = Pool::new();
+
+    let values = [pool.new_integer(0), pool.new_integer(-1), pool.new_symbol("nil"), pool.new_symbol("t")];
+    assert!(values.iter().all(|value| value.is_immediate()));
+    assert_eq!(pool.stats().allocations, 0);
+
+    let list = values.iter().fold(pool.new_symbol("nil"), |list, value| pool.new_cons(value.clone(), list));
+    assert_eq!(pool.stats().live, 4);
+
+    drop(list);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn nested_release() {
+    let pool: Pool<'_, 64> = Pool::new();
+
+    let mut tree = pool.new_symbol("nil");
+    for n in 0..16 {
+        let leaf = pool.new_cons(pool.new_number(n as f64), pool.new_symbol("nil"));
+        tree = pool.new_cons(leaf, tree);
+    }
+    let shared = tree.clone();
+    assert_eq!(pool.stats().live, 48);
+
+    drop(tree);
+    assert_eq!(pool.stats().live, 48);
+
+    drop(shared);
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn static_pool_is_taken_once() {
+    static POOL: StaticPool<8> = StaticPool::new();
+
+    let pool = POOL.take().unwrap();
+    assert!(POOL.take().is_none());
+
+    let list = pool.new_cons(pool.new_number(1.0), pool.new_symbol("nil"));
+    assert_eq!(pool.stats().live, 2);
+
+    drop(list);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[cfg(feature = "alloc")]
+mod growable {
+    use myser::{pool::{GrowablePool, Heap, CHUNK}, value::Value};
+
+    #[test]
+    fn grows_by_chunks() {
+        let pool = GrowablePool::new();
+        assert_eq!(pool.stats().capacity, 0);
+
+        let values: Vec<_> = (0..CHUNK * 3 + 1).map(|n| pool.new_number(n as f64)).collect();
+        assert_eq!(pool.stats().capacity, CHUNK * 4);
+        assert_eq!(pool.stats().live, CHUNK * 3 + 1);
+        assert_eq!(pool.verify(), Ok(()));
+
+        drop(values);
+        assert_eq!(pool.stats().live, 0);
+        assert_eq!(pool.stats().high_water, CHUNK * 3 + 1);
+        assert_eq!(pool.verify(), Ok(()));
+    }
+
+    #[test]
+    fn reuses_cells_in_earlier_chunks() {
+        let pool = GrowablePool::new();
+
+        let mut values: Vec<_> = (0..CHUNK * 2).map(|n| pool.new_number(n as f64)).collect();
+        values.drain(..CHUNK / 2);
+        values.extend((0..CHUNK / 2).map(|n| pool.new_number(n as f64)));
+
+        assert_eq!(pool.stats().capacity, CHUNK * 2);
+        assert_eq!(pool.verify(), Ok(()));
+    }
+
+    #[test]
+    fn stops_at_limit() {
+        let pool = GrowablePool::with_limit(CHUNK + 1);
+
+        let values: Vec<_> = (0..CHUNK * 2).map(|n| pool.new_number(n as f64)).collect();
+        assert!(pool.alloc(Value::Number(0.0)).is_err());
+        assert_eq!(pool.stats().failed_allocations, 1);
+
+        drop(values);
+        assert!(pool.alloc(Value::Number(0.0)).is_ok());
+    }
+}
+
+#[cfg(feature = "debug")]
+mod debug {
+    use myser::pool::{Heap, Pool};
+    use core::mem::ManuallyDrop;
+    use core::ptr;
+
+    #[test]
+    #[should_panic(expected = "use after free")]
+    fn stale_handle() {
+        let pool: Pool<'_, 4> = Pool::new();
+
+        let value = pool.new_number(1.0);
+        let stale = ManuallyDrop::new(unsafe { ptr::read(&value) });
+        drop(value);
+
+        let _reused = pool.new_number(2.0);
+        let _ = **stale;
+    }
+
+    #[test]
+    #[should_panic(expected = "refcount underflow")]
+    fn double_drop() {
+        let pool: Pool<'_, 4> = Pool::new();
+
+        let value = pool.new_number(1.0);
+        let duplicate = unsafe { ptr::read(&value) };
+        drop(value);
+        drop(duplicate);
+    }
+}
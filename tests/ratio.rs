@@ -0,0 +1,75 @@
+use myser::{eval::Cells, pool::Pool};
+
+mod common;
+use common::{run_alone, show, unhandled};
+
+#[test]
+fn uneven_division_is_exact() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(/ 1 2)")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "(/ 5)")), "1/5");
+    assert_eq!(show(&run_alone(&pool, "(/ 6 -4)")), "-3/2");
+    assert_eq!(show(&run_alone(&pool, "(/ 6 3)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(/ 1 2 3)")), "1/6");
+}
+
+#[test]
+fn ratios_take_part_in_arithmetic() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(+ 1/3 1/6)")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "(- 1/2 1/2)")), "0");
+    assert_eq!(show(&run_alone(&pool, "(* 2/3 3/2)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(+ 1 1/2)")), "3/2");
+    assert_eq!(show(&run_alone(&pool, "(/ 1/2 2)")), "1/4");
+    assert_eq!(show(&run_alone(&pool, "(+ 1/2 0.25)")), "0.75");
+}
+
+#[test]
+fn ratio_literals_are_normalized() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "2/4")), "1/2");
+    assert_eq!(show(&run_alone(&pool, "-3/9")), "-1/3");
+    assert_eq!(show(&run_alone(&pool, "8/4")), "2");
+}
+
+#[test]
+fn comparisons_are_exact() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(< 1/3 1/2 1)")), "t");
+    assert_eq!(show(&run_alone(&pool, "(< 1/3 1/2 1/2)")), "nil");
+    assert_eq!(show(&run_alone(&pool, "(<= 1/3 1/2 1/2)")), "t");
+    assert_eq!(show(&run_alone(&pool, "(= 1/2 2/4 0.5)")), "t");
+    assert_eq!(show(&run_alone(&pool, "(> 9007199254740993 9007199254740992)")), "t");
+    assert_eq!(show(&run_alone(&pool, "(>= 1 1/2 0)")), "t");
+    assert_eq!(show(&run_alone(&pool, "(/= 1 2 1)")), "nil");
+    assert_eq!(show(&run_alone(&pool, "(/= 1 2 3)")), "t");
+}
+
+#[test]
+fn conversions() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(show(&run_alone(&pool, "(numerator -6/4)")), "-3");
+    assert_eq!(show(&run_alone(&pool, "(denominator -6/4)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(denominator 5)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(floor -7/2)")), "-4");
+    assert_eq!(show(&run_alone(&pool, "(floor 2.5)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(floor -2.5)")), "-3");
+    assert_eq!(show(&run_alone(&pool, "(round 5/2)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(round 7/2)")), "4");
+    assert_eq!(show(&run_alone(&pool, "(round -5/2)")), "-2");
+    assert_eq!(show(&run_alone(&pool, "(round 2/3)")), "1");
+    assert_eq!(show(&run_alone(&pool, "(round 2.5)")), "2");
+    assert_eq!(show(&run_alone(&pool, "(round -3.5)")), "-4");
+}
+
+#[test]
+fn dividing_by_zero_is_an_error() {
+    let pool: Pool<'_, 256> = Pool::new();
+
+    assert_eq!(unhandled(&pool, &mut Cells::new(), "(/ 1 0)"), "division-by-zero");
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "sync")]
+
+use myser::{pool::{Heap, SyncPool}, value::Value};
+use core::ops::Deref;
+use std::{sync::mpsc, thread};
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 5000;
+
+fn length(mut list: &Value<'_>) -> usize {
+    let mut length = 0;
+    while let Value::Cons(_, cdr) = list {
+        length += 1;
+        list = cdr.deref();
+    }
+
+    length
+}
+
+#[test]
+fn allocate_and_drop_across_threads() {
+    let pool: SyncPool<'_, 4096> = SyncPool::new();
+    let pool = &pool;
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(64);
+
+        for thread in 0..THREADS {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                for round in 0..ROUNDS {
+                    let mut list = pool.new_symbol("nil");
+                    for n in 0..4 {
+                        list = pool.new_cons(pool.new_number((thread * ROUNDS + round + n) as f64), list);
+                    }
+
+                    // Every other list is dropped on the receiving thread instead.
+                    if round % 2 == 0 {
+                        sender.send(list).unwrap();
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        scope.spawn(move || {
+            for list in receiver {
+                assert_eq!(length(&list), 4);
+            }
+        });
+    });
+
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
+
+#[test]
+fn share_one_value_between_threads() {
+    let pool: SyncPool<'_, 64> = SyncPool::new();
+    let pool = &pool;
+
+    let mut list = pool.new_symbol("nil");
+    for n in 0..8 {
+        list = pool.new_cons(pool.new_number(n as f64), list);
+    }
+    let list = &list;
+
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(move || {
+                for _ in 0..ROUNDS {
+                    let copies: Vec<_> = (0..4).map(|_| list.clone()).collect();
+                    assert!(copies.iter().all(|copy| length(copy) == 8));
+                }
+            });
+        }
+    });
+
+    assert_eq!(pool.stats().live, 16);
+    assert_eq!(pool.verify(), Ok(()));
+}
@@ -0,0 +1,135 @@
+use myser::{eval::Cells, pool::{Heap, Pool}, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, run, unhandled};
+
+#[test]
+fn binding_and_looking_up() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set h (make-hash-table 8))");
+    assert_eq!(integer(&run(&pool, &mut cells, "(puthash 'a 1 h)")), 1);
+    run(&pool, &mut cells, "(puthash 2 'two h)");
+    run(&pool, &mut cells, "(puthash \"key\" 3 h)");
+    run(&pool, &mut cells, "(puthash '(1 (2 3)) 4 h)");
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(gethash 'a h)")), 1);
+    assert!(matches!(run(&pool, &mut cells, "(gethash 2 h)").deref(), Value::Symbol("two")));
+    assert_eq!(integer(&run(&pool, &mut cells, "(gethash (string \"key\") h)")), 3);
+    assert_eq!(integer(&run(&pool, &mut cells, "(gethash (list 1 (list 2 3)) h)")), 4);
+    assert!(matches!(run(&pool, &mut cells, "(gethash 'b h)").deref(), Value::Symbol("nil")));
+    assert_eq!(integer(&run(&pool, &mut cells, "(gethash 'b h 0)")), 0);
+    assert_eq!(integer(&run(&pool, &mut cells, "(hash-table-count h)")), 4);
+
+    run(&pool, &mut cells, "(puthash 'a 5 h)");
+    assert_eq!(integer(&run(&pool, &mut cells, "(gethash 'a h)")), 5);
+    assert_eq!(integer(&run(&pool, &mut cells, "(hash-table-count h)")), 4);
+}
+
+#[test]
+fn removing_keeps_the_rest_reachable() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set h (make-hash-table 16))");
+    for n in 0..16 {
+        let source = format!("(puthash {} {} h)", n, n * n);
+        run(&pool, &mut cells, Box::leak(source.into_boxed_str()));
+    }
+
+    for n in (0..16).step_by(3) {
+        let source = format!("(remhash {} h)", n);
+        assert!(matches!(run(&pool, &mut cells, Box::leak(source.into_boxed_str())).deref(), Value::Symbol("t")));
+    }
+    assert!(matches!(run(&pool, &mut cells, "(remhash 0 h)").deref(), Value::Symbol("nil")));
+    assert_eq!(integer(&run(&pool, &mut cells, "(hash-table-count h)")), 10);
+
+    for n in 0..16 {
+        let source = format!("(gethash {} h -1)", n);
+        let expected = if n % 3 == 0 { -1 } else { n * n };
+        assert_eq!(integer(&run(&pool, &mut cells, Box::leak(source.into_boxed_str()))), expected);
+    }
+}
+
+#[test]
+fn maphash_visits_every_entry() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+    let mut cells = Cells::new();
+
+    let result = run(&pool, &mut cells, "(progn
+        (defmacro tally (k v) (list 'set 'sum (list '+ 'sum k v)))
+        (set sum 0)
+        (set h (make-hash-table 4))
+        (puthash 1 10 h)
+        (puthash 2 20 h)
+        (puthash 3 30 h)
+        (maphash 'tally h)
+        sum)");
+    assert_eq!(integer(&result), 66);
+}
+
+#[test]
+fn a_fixed_table_refuses_entries_past_its_capacity() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set h (make-hash-table 2))");
+    run(&pool, &mut cells, "(puthash 1 1 h)");
+    run(&pool, &mut cells, "(puthash 2 2 h)");
+    run(&pool, &mut cells, "(puthash 2 3 h)");
+    assert_eq!(unhandled(&pool, &mut cells, "(puthash 3 3 h)"), "storage-condition: hash table is full");
+}
+
+#[test]
+fn tables_with_no_room_are_storage_conditions() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (make-hash-table 100000) (storage-condition () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (progn (set h (make-hash-table)) (dotimes (i 200) (puthash i i h))) (storage-condition () 2))")), 2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn a_default_table_grows() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set h (make-hash-table))");
+    for n in 0..40 {
+        let source = format!("(puthash {} {} h)", n, -n);
+        run(&pool, &mut cells, Box::leak(source.into_boxed_str()));
+    }
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(hash-table-count h)")), 40);
+    for n in 0..40 {
+        let source = format!("(gethash {} h)", n);
+        assert_eq!(integer(&run(&pool, &mut cells, Box::leak(source.into_boxed_str()))), -n);
+    }
+}
+
+#[test]
+fn entries_are_released_with_the_table() {
+    let pool: Pool<'_, 256, 16384> = Pool::new();
+
+    let table = pool.new_table(4, true).unwrap();
+    let Value::Table(entries) = table.deref() else { panic!("{:?}", table) };
+    for n in 0..8 {
+        if let Err((key, value)) = entries.insert(pool.new_integer(n), pool.new_string("value").unwrap()) {
+            let capacity = entries.capacity() * 2;
+            entries.grow(pool.alloc_block(myser::table::Table::size(capacity).unwrap()).unwrap(), capacity);
+            entries.insert(key, value).unwrap();
+        }
+    }
+    assert_eq!(entries.len(), 8);
+    assert!(entries.remove(&Value::Integer(3)).is_some());
+    assert_eq!(pool.stats().live, 8);
+
+    drop(table);
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}
@@ -0,0 +1,101 @@
+use myser::{eval::Cells, pool::Pool, value::KINDS};
+
+mod common;
+use common::{run, symbol};
+
+/// An expression making a value of each kind, in the order of `KINDS`.
+#[cfg(not(feature = "bigint"))]
+const EXAMPLES: [&str; 10] = ["1", "1.5", "\"s\"", "'s", "'(1)", "1/2", "#\\a", "#(1)", "(make-hash-table 1)", "#u8(01)"];
+#[cfg(feature = "bigint")]
+const EXAMPLES: [&str; 11] = ["1", "1.5", "\"s\"", "'s", "'(1)", "1/2", "#\\a", "#(1)", "(make-hash-table 1)", "#u8(01)", "(* 4294967296 4294967296)"];
+
+#[test]
+fn type_of_names_every_kind() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    for (example, kind) in EXAMPLES.iter().zip(KINDS) {
+        let source = format!("(type-of {})", example);
+        assert_eq!(symbol(&run(&pool, &mut cells, Box::leak(source.into_boxed_str()))), kind);
+    }
+
+    assert_eq!(symbol(&run(&pool, &mut cells, "(type-of nil)")), "symbol");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(type-of -7)")), "integer");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(type-of 1.5)")), "float");
+}
+
+#[test]
+fn type_of_agrees_with_the_predicates() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    // Each predicate and the kinds it holds for.
+    let predicates = [
+        ("integerp", &["integer", "bignum"][..]),
+        ("floatp", &["float"][..]),
+        ("numberp", &["integer", "float", "ratio", "bignum"][..]),
+        ("symbolp", &["symbol"][..]),
+        ("stringp", &["string"][..]),
+        ("consp", &["cons"][..]),
+    ];
+
+    for example in EXAMPLES {
+        let kind = run(&pool, &mut cells, Box::leak(format!("(type-of {})", example).into_boxed_str()));
+        let kind = symbol(&kind);
+        for (predicate, kinds) in predicates {
+            let source: &str = Box::leak(format!("({} {})", predicate, example).into_boxed_str());
+            let expected = if kinds.contains(&kind) { "t" } else { "nil" };
+            assert_eq!(symbol(&run(&pool, &mut cells, source)), expected, "{} is a {}", source, kind);
+        }
+    }
+}
+
+#[test]
+fn predicates() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    let cases = [
+        ("integerp", ["1", "(- 5)"], ["1.0", "1/2"]),
+        ("floatp", ["1.0", "+nan.0"], ["1", "'x"]),
+        ("numberp", ["1", "1/2"], ["\"1\"", "#\\1"]),
+        ("symbolp", ["'x", "nil"], ["\"x\"", "'(x)"]),
+        ("stringp", ["\"x\"", "(string #\\x)"], ["'x", "#\\x"]),
+        ("consp", ["'(1)", "(list 1 2)"], ["nil", "#(1)"]),
+        ("listp", ["'(1)", "nil"], ["'x", "#(1)"]),
+        ("atom", ["nil", "#(1)"], ["'(1)", "(list nil)"]),
+        ("null", ["nil", "'()"], ["'nil-not", "0"]),
+        ("functionp", ["'car", "'functionp"], ["'no-such-function", "1"]),
+    ];
+
+    for (predicate, holds, fails) in cases {
+        for example in holds {
+            let source: &str = Box::leak(format!("({} {})", predicate, example).into_boxed_str());
+            assert_eq!(symbol(&run(&pool, &mut cells, source)), "t", "{}", source);
+        }
+        for example in fails {
+            let source: &str = Box::leak(format!("({} {})", predicate, example).into_boxed_str());
+            assert_eq!(symbol(&run(&pool, &mut cells, source)), "nil", "{}", source);
+        }
+    }
+}
+
+#[test]
+fn macros_are_functions() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(symbol(&run(&pool, &mut cells, "(functionp 'twice)")), "nil");
+    run(&pool, &mut cells, "(defmacro twice (x) (list '* 2 x))");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(functionp 'twice)")), "t");
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn bignums_are_integers() {
+    let pool: Pool<'_, 256, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(symbol(&run(&pool, &mut cells, "(integerp (* 4294967296 4294967296))")), "t");
+    assert_eq!(symbol(&run(&pool, &mut cells, "(numberp (* 4294967296 4294967296))")), "t");
+}
@@ -0,0 +1,71 @@
+use myser::{eval::Cells, pool::{Heap, Pool}, value::Value};
+use core::ops::Deref;
+
+mod common;
+use common::{integer, integers, run, unhandled};
+
+#[test]
+fn literals_and_constructors() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integers(&run(&pool, &mut cells, "#(1 2 3)")), [1, 2, 3]);
+    assert_eq!(integers(&run(&pool, &mut cells, "#()")), []);
+    assert_eq!(integers(&run(&pool, &mut cells, "(vector 1 (+ 1 1))")), [1, 2]);
+    assert_eq!(integers(&run(&pool, &mut cells, "(make-vector 3 7)")), [7, 7, 7]);
+    assert_eq!(integers(&run(&pool, &mut cells, "(list->vector '(4 5))")), [4, 5]);
+}
+
+#[test]
+fn indexing_and_replacing() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    run(&pool, &mut cells, "(set v (make-vector 4 0))");
+    run(&pool, &mut cells, "(vector-set! v 2 5)");
+    assert_eq!(integers(&run(&pool, &mut cells, "v")), [0, 0, 5, 0]);
+    assert!(matches!(run(&pool, &mut cells, "(vector-ref v 2)").deref(), Value::Integer(5)));
+    assert!(matches!(run(&pool, &mut cells, "(vector-ref v 4)").deref(), Value::Symbol("nil")));
+    assert!(matches!(run(&pool, &mut cells, "(vector-length v)").deref(), Value::Integer(4)));
+
+    let list = run(&pool, &mut cells, "(vector->list #(1 2))");
+    assert_eq!(format!("{:?}", list), "Cons(Integer(1), Cons(Integer(2), Symbol(\"nil\")))");
+}
+
+#[test]
+fn setting_past_the_end_is_an_error() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(unhandled(&pool, &mut cells, "(vector-set! #(1) 1 0)"), "type-error: out of range");
+}
+
+#[test]
+fn vectors_with_no_room_are_storage_conditions() {
+    let pool: Pool<'_, 64, 64> = Pool::new();
+    let mut cells = Cells::new();
+
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (make-vector 100000 0) (storage-condition () 1))")), 1);
+    assert_eq!(integer(&run(&pool, &mut cells, "(handler-case (vector 1 2 3 4 5 6 7 8 9 10) (storage-condition () 2))")), 2);
+    assert_eq!(integer(&run(&pool, &mut cells,
+        "(handler-case (list->vector '(1 2 3 4 5 6 7 8 9 10)) (storage-condition () 3))")), 3);
+}
+
+#[test]
+fn elements_are_released_with_the_vector() {
+    let pool: Pool<'_, 64, 1024> = Pool::new();
+
+    let string = pool.new_string("shared").unwrap();
+    let vector = pool.new_vector(3, &mut |_| string.clone()).unwrap();
+    drop(string);
+    assert_eq!(pool.stats().live, 2);
+
+    let Value::Vector(elements) = vector.deref() else { panic!("{:?}", vector) };
+    elements.set(0, pool.new_number(1.0)).unwrap();
+    assert_eq!(pool.stats().live, 3);
+
+    drop(vector);
+    assert_eq!(pool.stats().live, 0);
+    assert_eq!(pool.stats().arena_used, 0);
+    assert_eq!(pool.verify(), Ok(()));
+}